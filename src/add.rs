@@ -1,49 +1,115 @@
-use std::path::Path;
-
-use anyhow::{Context, Result, anyhow};
+use std::path::{Path, PathBuf};
 
 use crate::{
+    error::{Error, Result},
     executor::Executor,
     file_kind::{exists, is_symlink},
+    git::ensure_work_tree,
+    report::*,
+    secret::SECRET_SUFFIX,
+    secret_scan::scan_file,
 };
 
-pub fn add(executor: impl Executor, path: impl AsRef<Path>) -> Result<()> {
+#[derive(Debug, Clone, Default)]
+pub struct AddOptions {
+    // 暗号化してdotfilesに加える(`secrets.encrypt_command`を使う)．
+    // `secrets.files`に含まれるファイルはこの指定がなくても暗号化する．
+    pub encrypt: bool,
+
+    // 秘密情報らしきものを含んでいても平文のまま加える．
+    pub allow_secrets: bool,
+}
+
+pub fn add(
+    executor: &impl Executor,
+    path: impl AsRef<Path>,
+    options: &AddOptions,
+) -> Result<AddReport> {
+    let mut report = AddReport::default();
+
     let path = path.as_ref();
 
     if is_symlink(path) {
-        eprintln!("[warning] {} is a symlink. skipped.", path.display());
-        return Ok(());
+        report.skipped.push(Skipped {
+            path: path.to_path_buf(),
+            reason: SkipReason::Symlink,
+        });
+        return Ok(report);
     }
 
     let path = path
         .canonicalize()
-        .with_context(|| format!("invalid path: {}", path.display()))?;
-
-    if !path.starts_with(executor.home_dir()) {
-        return Err(anyhow!(
-            "{} is not in {}.",
-            path.display(),
-            executor.home_dir().display()
-        ));
+        .map_err(|e| Error::io("resolve path", path, e))?;
+
+    if executor.config().target_of_dest(&path).is_none() {
+        return Err(Error::OutOfScope {
+            path,
+            scope: executor.home_dir().to_path_buf(),
+        });
     }
 
     // dotfiles管理下ならスキップ．
-    if path.starts_with(executor.dotfiles_home_dir()) {
-        return Err(anyhow!(
-            "{} is in {}.",
-            path.display(),
-            executor.dotfiles_home_dir().display()
-        ));
+    if let Some(target) = executor.config().target_of_source(&path) {
+        return Err(Error::AlreadyManaged {
+            path,
+            dotfiles_home_dir: target.source.clone(),
+        });
     }
 
     let to = executor.entity_path(&path)?;
+    let to_encrypted = PathBuf::from({
+        let mut to = to.clone().into_os_string();
+        to.push(SECRET_SUFFIX);
+        to
+    });
+
+    // 平文と暗号文のどちらかがあれば，同じパスにinstallされるのでスキップ．
+    if let Some(existing) = [&to, &to_encrypted].into_iter().find(|to| exists(to)) {
+        report.skipped.push(Skipped {
+            path: existing.clone(),
+            reason: SkipReason::AlreadyExists,
+        });
+        return Ok(report);
+    }
+
+    // ファイルを加えた後にコミットできないと分からないよう，先に確かめる．
+    if executor.auto_commit() {
+        ensure_work_tree(executor.dotfiles_dir())?;
+    }
+
+    let encrypt = options.encrypt || executor.config().is_secret(&to);
+
+    // 平文のまま加える場合は秘密情報が含まれていないか確認する．
+    if !encrypt && !options.allow_secrets {
+        let findings = scan_file(&path)?;
+        if !findings.is_empty() {
+            return Err(Error::SecretDetected { path, findings });
+        }
+    }
+
+    let to = if executor.config().is_secret(&to) {
+        report.encrypted = true;
+        executor.encrypt_to_dotfiles_home(&path, &to)?;
+        to
+    } else if options.encrypt {
+        report.encrypted = true;
+        executor.encrypt_to_dotfiles_home(&path, &to_encrypted)?;
+        to_encrypted
+    } else {
+        executor.copy(&path, &to)?;
+        to
+    };
 
-    if exists(&to) {
-        eprintln!("[warning] {} already exists. skipped.", to.display());
-        return Ok(());
+    if executor.auto_commit() {
+        let message = format!(
+            "add {}",
+            executor.repo_rel_from_dotfiles_home(&to)?.display()
+        );
+        executor.git_commit(std::slice::from_ref(&to), &message)?;
+        report.commit = Some(message);
     }
 
-    executor.copy(path, to)?;
+    report.copied = Some(PathPair { from: path, to });
 
-    Ok(())
+    Ok(report)
 }
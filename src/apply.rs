@@ -0,0 +1,75 @@
+use crate::{
+    error::{Error, Result},
+    executor::{Backup, Executor, PlanExecutor},
+    hooks::{Hook, HookEvent, run_hook},
+    plan::Plan,
+    report::{ApplyReport, ChangedPaths, PathPair},
+};
+
+/// 保存された計画を適用する．
+/// ファイルの状態が計画作成時から変わっている場合は何もせずエラーを返す．
+pub fn apply(executor: &impl Executor, plan: &Plan) -> Result<ApplyReport> {
+    if plan.home_dir != executor.home_dir() {
+        return Err(Error::PlanMismatch {
+            key: "home",
+            planned: plan.home_dir.clone(),
+            configured: executor.home_dir().to_path_buf(),
+        });
+    }
+
+    if plan.dotfiles_home_dir != executor.dotfiles_home_dir() {
+        return Err(Error::PlanMismatch {
+            key: "dotfiles",
+            planned: plan.dotfiles_home_dir.clone(),
+            configured: executor.dotfiles_home_dir().to_path_buf(),
+        });
+    }
+
+    plan.verify()?;
+
+    let mut report = ApplyReport::default();
+
+    for step in &plan.steps {
+        if let Some(Backup { path, created_dir }) = step.operation.perform(executor)? {
+            if let Some(dir) = created_dir
+                && !report.created_dirs.contains(&dir)
+            {
+                report.created_dirs.push(dir);
+            }
+            report.backups.push(PathPair {
+                from: step.operation.paths()[0].to_path_buf(),
+                to: path,
+            });
+        }
+        report.performed.push(step.operation.clone());
+    }
+
+    Ok(report)
+}
+
+/// `plan`で計画を1度だけ作り，その変更予定のパスで`event`のpre-hookを実行してから`executor`で計画を適用する．
+/// 返すのは計画作成時の処理予定内容．`dry_run`はpre-hookに渡す．
+///
+/// # NOTE
+/// pre-hookが計画の対象のファイルを変更した場合は，適用時に計画との不一致としてエラーになる．
+pub fn apply_planned<R: ChangedPaths>(
+    executor: &impl Executor,
+    event: HookEvent,
+    dry_run: bool,
+    plan: impl FnOnce(&PlanExecutor) -> Result<R>,
+) -> Result<R> {
+    let planner = PlanExecutor::new(executor.config().clone());
+    let report = plan(&planner)?;
+    let plan = planner.into_plan();
+
+    run_hook(
+        executor.config(),
+        Hook::pre(event),
+        &report.changed_paths(),
+        dry_run,
+    )?;
+
+    apply(executor, &plan)?;
+
+    Ok(report)
+}
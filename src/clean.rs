@@ -0,0 +1,133 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::Config, error::Result, executor::Executor, file_kind::*, report::CleanReport,
+    revision::revisions_dir,
+};
+
+/// `link`がシンボリックリンクならその参照先の絶対パスを返す．
+pub(crate) fn link_destination(link: impl AsRef<Path>) -> Option<PathBuf> {
+    let link = link.as_ref();
+
+    let raw_destination = fs::read_link(link).ok()?;
+
+    if raw_destination.is_absolute() {
+        Some(raw_destination)
+    } else {
+        Some(link.parent()?.join(raw_destination))
+    }
+}
+
+/// 各`[[target]]`の`source`以下のディレクトリに対応するインストール先のディレクトリを調べ，
+/// 各`source`以下と`install --rev`で取り出したrevision以下を指す壊れたリンクと，
+/// `[[link]]`で宣言した壊れたリンクを削除する．
+///
+/// # NOTE
+/// - リンクはミラー内のディレクトリに対応する場所にしか作られないので，$HOME全体は走査しない．
+///   ディレクトリごと削除されたファイルを指すリンクは対象外(`sync`はupstreamで削除されたものを削除する)．
+/// - dotfilesレポジトリ，バックアップディレクトリ，取り出したrevisionの中は調べない．
+/// - ディレクトリへのシンボリックリンクは辿らない．
+pub fn clean(executor: &impl Executor) -> Result<CleanReport> {
+    let mut report = CleanReport::default();
+
+    let backup_root = executor
+        .backup_dir()
+        .parent()
+        .unwrap_or(executor.backup_dir());
+    let revisions = revisions_dir(executor.config());
+    let skip_dirs = [executor.dotfiles_dir(), backup_root, &revisions];
+
+    for dir in mirrored_dirs(executor.config(), &mut report.warnings) {
+        if skip_dirs.iter().any(|skip| dir.starts_with(skip)) || file_kind(&dir) != FileKind::Dir {
+            continue;
+        }
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                report
+                    .warnings
+                    .push(format!("failed to read_dir {}: {}", dir.display(), e));
+                continue;
+            }
+        };
+
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    report.warnings.push(format!(
+                        "failed to read entry in {}: {}",
+                        dir.display(),
+                        e
+                    ));
+                    continue;
+                }
+            };
+
+            if file_kind(&path) != FileKind::Symlink {
+                continue;
+            }
+            let Some(destination) = link_destination(&path) else {
+                continue;
+            };
+
+            let managed = executor.config().target_of_source(&destination).is_some()
+                || destination.starts_with(&revisions);
+            if managed && is_broken_link(&path) {
+                executor.remove_symlink_from_home(&path)?;
+                report.removed_links.push(path);
+            }
+        }
+    }
+
+    // `[[link]]`のインストール先は走査範囲の外にあることがあるので個別に確認する．
+    for link in &executor.config().links {
+        if is_broken_link(&link.dst)
+            && link_destination(&link.dst).as_ref() == Some(&link.src)
+            && !report.removed_links.contains(&link.dst)
+        {
+            executor.remove_symlink_from_home(&link.dst)?;
+            report.removed_links.push(link.dst.clone());
+        }
+    }
+
+    report.removed_links.sort_unstable();
+
+    Ok(report)
+}
+
+/// 各`source`以下のディレクトリ(`source`自身を含む)に対応するインストール先のディレクトリ．
+fn mirrored_dirs(config: &Config, warnings: &mut Vec<String>) -> Vec<PathBuf> {
+    let mut dirs = vec![];
+
+    for target in &config.targets {
+        let mut stack = vec![target.source.clone()];
+        while let Some(dir) = stack.pop() {
+            if let Ok(rel) = dir.strip_prefix(&target.source) {
+                dirs.push(target.dest.join(rel));
+            }
+
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warnings.push(format!("failed to read_dir {}: {}", dir.display(), e));
+                    continue;
+                }
+            };
+            stack.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| file_kind(path) == FileKind::Dir),
+            );
+        }
+    }
+
+    dirs.sort_unstable();
+    dirs.dedup();
+    dirs
+}
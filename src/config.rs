@@ -1,29 +1,163 @@
 //! 設定ファイル(toml)から設定を読み込む．
 
 use std::{
-    fs,
+    collections::BTreeMap,
+    env,
     path::{Path, PathBuf},
 };
 
-use anyhow::{Context, Result, anyhow};
 use chrono::Local;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::{
+    config_check::ConfigCheck,
+    config_file::LayeredConfig,
+    error::{Error, Result},
+    hooks::Trigger,
+    permissions::Permission,
+    secret::SECRET_SUFFIX,
+};
+
+/// パスを表す項目はすべて`~`，`$VAR`，`${VAR}`を展開する．
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct General {
-    #[serde(rename = "dotfiles")]
-    dotfiles_dir: PathBuf,
+    // 設定ファイル，`GeneralOverrides`のいずれかで指定する必要がある．
+    #[serde(rename = "dotfiles", default)]
+    dotfiles_dir: Option<String>,
 
-    #[serde(rename = "home")]
-    home_dir: PathBuf,
+    // 省略時は$HOME．
+    #[serde(rename = "home", default)]
+    home_dir: Option<String>,
 
-    #[serde(rename = "backup_dir")]
-    backup_dir: PathBuf,
+    // 省略時は`DEFAULT_BACKUP_DIR`．存在しなければ初めて退避するときに作成する．
+    #[serde(rename = "backup_dir", default)]
+    backup_dir: Option<String>,
+}
+
+/// コマンドラインオプション・環境変数で指定した`[general]`の値．設定ファイルの値より優先する．
+/// 相対パスはカレントディレクトリからの相対パスとする．
+#[derive(Debug, Clone, Default)]
+pub struct GeneralOverrides {
+    pub dotfiles_dir: Option<PathBuf>,
+    pub home_dir: Option<PathBuf>,
+    pub backup_dir: Option<PathBuf>,
+}
+
+const DEFAULT_HOME_DIR: &str = "~";
+const DEFAULT_BACKUP_DIR: &str = "${XDG_STATE_HOME}/dotkoke/backups";
+
+/// `[git]`セクション．
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitConfig {
+    // add/removeの後に変更をdotfilesレポジトリへコミットする．
+    #[serde(default)]
+    pub auto_commit: bool,
+}
+
+/// `[secrets]`セクション．
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecretsConfig {
+    // 暗号文を標準入力から受け取り，平文を標準出力に書くコマンド．`sh -c`で実行する．
+    #[serde(default)]
+    pub decrypt_command: Option<String>,
+
+    // 平文を標準入力から受け取り，暗号文を標準出力に書くコマンド．`sh -c`で実行する．
+    #[serde(default)]
+    pub encrypt_command: Option<String>,
+
+    // 接尾辞`.enc`が無くても暗号化されているものとして扱うファイル(dotfiles/homeからの相対パス)．
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+}
+
+/// `[hooks]`セクション．各コマンドは`sh -c`で実行する．
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HooksConfig {
+    #[serde(default)]
+    pub pre_install: Option<String>,
+
+    #[serde(default)]
+    pub post_install: Option<String>,
+
+    #[serde(default)]
+    pub pre_add: Option<String>,
+
+    #[serde(default)]
+    pub post_add: Option<String>,
+
+    #[serde(default)]
+    pub pre_remove: Option<String>,
+
+    #[serde(default)]
+    pub post_remove: Option<String>,
+}
+
+/// `[[target]]`の1項目．
+#[derive(Debug, Serialize, Deserialize)]
+struct TargetToml {
+    // dotfilesレポジトリのルートからの相対パス．
+    source: String,
+
+    // 相対パスの場合は`home`からの相対パス．
+    dest: String,
+}
+
+/// dotfilesレポジトリ内の`source`以下を`dest`以下のミラーとして扱う対応．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub source: PathBuf,
+    pub dest: PathBuf,
+}
+
+/// `[[link]]`の1項目．
+#[derive(Debug, Serialize, Deserialize)]
+struct LinkToml {
+    // dotfilesレポジトリのルートからの相対パス．
+    src: String,
+
+    // 相対パスの場合は`home`からの相対パス．
+    dst: String,
+}
+
+/// dotfilesレポジトリ内のファイル`src`を`dst`にインストールする宣言．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub src: PathBuf,
+    pub dst: PathBuf,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Toml {
+    // 設定ファイルの形式のバージョン．読み込み時に`CONFIG_VERSION`に移行済み．
+    #[serde(default)]
+    version: Option<u32>,
+
+    #[serde(default)]
     general: General,
+
+    #[serde(default)]
+    git: GitConfig,
+
+    #[serde(default)]
+    secrets: SecretsConfig,
+
+    #[serde(default)]
+    hooks: HooksConfig,
+
+    #[serde(default, rename = "target")]
+    targets: Vec<TargetToml>,
+
+    #[serde(default, rename = "link")]
+    links: Vec<LinkToml>,
+
+    // $HOMEからの相対パスまたはglob -> 変更時に実行するコマンド
+    #[serde(default)]
+    triggers: BTreeMap<String, String>,
+
+    // $HOMEからの相対パスまたはglob -> mode(`"0700"`のような8進数の文字列)
+    #[serde(default)]
+    permissions: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone)]
@@ -31,89 +165,611 @@ pub struct Config {
     // $HOME
     pub home_dir: PathBuf,
 
-    // 使うことなさそうなのでprivate
-    #[allow(dead_code)]
-    dotfiles_dir: PathBuf,
+    // dotfilesレポジトリのルート．
+    pub dotfiles_dir: PathBuf,
 
     // バックアップは`backup_dir/YYYYmmdd_HHMM`以下に保存する．
     pub backup_dir: PathBuf,
 
     // $HOMEのミラー．
     // dotfiles/home/
+    // `install --rev`の場合はキャッシュ内の作業ツリーのhome/になる．
+    // `[[target]]`を指定した場合は存在しなくてもよい．
     pub dotfiles_home_dir: PathBuf,
+
+    // dotfiles/homeから$HOMEへの対応(dotfiles/homeが存在する場合)と，`[[target]]`で指定した対応．
+    pub targets: Vec<Target>,
+
+    // `[[link]]`で宣言したリンク．`src`が存在するとは限らない．
+    pub links: Vec<Link>,
+
+    pub git: GitConfig,
+
+    pub secrets: SecretsConfig,
+
+    pub hooks: HooksConfig,
+
+    // `[triggers]`で指定したコマンド．
+    pub triggers: Vec<Trigger>,
+
+    // `[permissions]`で指定したmode．
+    pub permissions: Vec<Permission>,
+
+    // 読み込み時の警告(`Config::check`の警告を含む)．
+    pub warnings: Vec<String>,
 }
 
 impl Config {
     pub fn read(config_toml_path: impl AsRef<Path>) -> Result<Self> {
-        let config_toml_path = config_toml_path.as_ref();
+        Config::load(
+            Some(config_toml_path.as_ref()),
+            &GeneralOverrides::default(),
+        )
+    }
 
-        let toml_str = fs::read_to_string(config_toml_path).with_context(|| {
-            format!("failed to read config file: {}", config_toml_path.display())
-        })?;
+    /// 設定ファイル`config_toml_path`(無ければ空の設定)に`overrides`を適用して読み込む．
+    pub fn load(config_toml_path: Option<&Path>, overrides: &GeneralOverrides) -> Result<Self> {
+        let layered = match config_toml_path {
+            Some(path) => LayeredConfig::load(path)?,
+            None => LayeredConfig::default(),
+        };
 
+        // serdeが無視するキーはtypoの可能性があるので警告する．
+        let mut unknown_keys = vec![];
         let Toml {
+            version: _,
             general:
                 General {
                     dotfiles_dir,
                     home_dir,
                     backup_dir,
                 },
-        } = toml::from_str(&toml_str).with_context(|| {
-            format!(
-                "failed to parse config file: {}",
-                config_toml_path.display()
-            )
-        })?;
-
-        let dotfiles_dir = dotfiles_dir.canonicalize().with_context(|| {
-            format!(
-                "invalid dotfiles directory in config: {}",
-                dotfiles_dir.display()
-            )
+            git,
+            secrets,
+            hooks,
+            targets,
+            links,
+            triggers,
+            permissions,
+        } = serde_ignored::deserialize(toml::Value::Table(layered.table.clone()), |key| {
+            unknown_keys.push(key.to_string())
+        })
+        .map_err(|source| Error::ConfigParse {
+            path: config_toml_path.map(Path::to_path_buf).unwrap_or_default(),
+            source,
         })?;
 
-        if !dotfiles_dir.is_dir() {
-            return Err(anyhow!("{} is not directory.", dotfiles_dir.display()));
+        let mut warnings = layered.warnings.clone();
+        for key in unknown_keys {
+            match origin_of_key(&layered, &key) {
+                Some(origin) => warnings.push(format!(
+                    "unknown key {} in {}. ignored.",
+                    key,
+                    origin.display()
+                )),
+                None => warnings.push(format!("unknown key {} in config. ignored.", key)),
+            }
         }
 
-        let home_dir = home_dir
-            .canonicalize()
-            .with_context(|| format!("invalid home directory in config: {}", home_dir.display()))?;
+        let triggers = triggers
+            .into_iter()
+            .map(|(pattern, command)| Trigger { pattern, command })
+            .collect();
 
-        if !home_dir.is_dir() {
-            return Err(anyhow!("{} is not directory.", home_dir.display()));
-        }
+        let permissions = permissions
+            .into_iter()
+            .map(|(pattern, mode)| Permission::parse(pattern, &mode))
+            .collect::<Result<_>>()?;
+
+        // `[general]`の相対パスはその値を定義した設定ファイルのあるディレクトリからの相対パスとする．
+        // 設定ファイルのパスは`LayeredConfig`で正規化済みなので，
+        // シンボリックリンクの場合はリンク先のファイルのあるディレクトリになる．
+        let config_dir = |key: &str| {
+            layered
+                .origin(key)
+                .and_then(Path::parent)
+                .expect("config value should have origin file")
+                .to_path_buf()
+        };
+
+        let dotfiles_dir = match (&overrides.dotfiles_dir, dotfiles_dir) {
+            (Some(dotfiles_dir), _) => canonicalize_dir("dotfiles", dotfiles_dir.clone())?,
+            (None, Some(dotfiles_dir)) => canonicalize_dir(
+                "dotfiles",
+                config_dir("general.dotfiles").join(expand_path("dotfiles", &dotfiles_dir)?),
+            )?,
+            (None, None) => return Err(Error::MissingDotfilesDir),
+        };
 
-        let backup_dir = backup_dir
-            .canonicalize()
-            .with_context(|| {
-                format!(
-                    "invalid backup directory in config: {}",
-                    backup_dir.display()
-                )
-            })?
-            .join(Local::now().format("%Y%m%d_%H%M").to_string());
+        let home_dir = match (&overrides.home_dir, home_dir) {
+            (Some(home_dir), _) => canonicalize_dir("home", home_dir.clone())?,
+            (None, Some(home_dir)) => canonicalize_dir(
+                "home",
+                config_dir("general.home").join(expand_path("home", &home_dir)?),
+            )?,
+            (None, None) => canonicalize_dir("home", expand_path("home", DEFAULT_HOME_DIR)?)?,
+        };
+
+        let backup_dir = match (&overrides.backup_dir, backup_dir) {
+            (Some(backup_dir), _) => canonicalize_dir("backup", backup_dir.clone())?,
+            (None, Some(backup_dir)) => canonicalize_dir(
+                "backup",
+                config_dir("general.backup_dir").join(expand_path("backup_dir", &backup_dir)?),
+            )?,
+            (None, None) => {
+                let backup_dir = expand_path("backup_dir", DEFAULT_BACKUP_DIR)?;
+                backup_dir.canonicalize().unwrap_or(backup_dir)
+            }
+        }
+        .join(Local::now().format("%Y%m%d_%H%M").to_string());
 
         if backup_dir.is_dir() {
-            eprintln!("[warning] {} already exists.", backup_dir.display());
+            warnings.push(format!("{} already exists.", backup_dir.display()));
         }
 
-        let dotfiles_home_dir = dotfiles_dir
-            .join("home")
-            .canonicalize()
-            .with_context(|| format!("invalid path: {}/home", dotfiles_dir.display()))?;
+        let dotfiles_home_dir = dotfiles_dir.join("home");
 
-        if !dotfiles_home_dir.is_dir() {
-            return Err(anyhow!("{} is not directory.", dotfiles_home_dir.display()));
+        let mut configured_targets = vec![];
+        for TargetToml { source, dest } in targets {
+            let source = canonicalize_dir(
+                "target.source",
+                dotfiles_dir.join(expand_path("target.source", &source)?),
+            )?;
+            let dest = home_dir.join(expand_path("target.dest", &dest)?);
+            // まだ存在しないインストール先は作成時に親ディレクトリごと作られる．
+            let dest = dest.canonicalize().unwrap_or(dest);
+            configured_targets.push(Target { source, dest });
         }
 
-        let config = Config {
+        let links = links
+            .into_iter()
+            .map(|LinkToml { src, dst }| {
+                Ok(Link {
+                    src: dotfiles_dir.join(expand_path("link.src", &src)?),
+                    // `[[target]]`の`dest`と比較できるように正規化する．
+                    dst: canonicalize_parent(home_dir.join(expand_path("link.dst", &dst)?)),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        // `[[target]]`が無ければ従来通りdotfiles/homeを必須とする．
+        let dotfiles_home_dir = if configured_targets.is_empty() || dotfiles_home_dir.exists() {
+            canonicalize_dir("dotfiles/home", dotfiles_home_dir)?
+        } else {
+            dotfiles_home_dir
+        };
+
+        let mut targets = vec![];
+        if dotfiles_home_dir.is_dir() {
+            targets.push(Target {
+                source: dotfiles_home_dir.clone(),
+                dest: home_dir.clone(),
+            });
+        }
+        targets.extend(configured_targets);
+
+        let mut config = Config {
             dotfiles_dir,
             home_dir,
             backup_dir,
             dotfiles_home_dir,
+            targets,
+            links,
+            git,
+            secrets,
+            hooks,
+            triggers,
+            permissions,
+            warnings,
         };
 
+        let ConfigCheck { errors, warnings } = config.check();
+        if !errors.is_empty() {
+            return Err(Error::InvalidDirectoryLayout { problems: errors });
+        }
+        config.warnings.extend(warnings);
+
         Ok(config)
     }
 }
+
+impl Config {
+    /// dotfilesレポジトリ内の`path`を含む対応．複数ある場合は`source`が最も深いもの．
+    pub fn target_of_source(&self, path: impl AsRef<Path>) -> Option<&Target> {
+        let path = path.as_ref();
+        self.targets
+            .iter()
+            .filter(|target| path.starts_with(&target.source))
+            .max_by_key(|target| target.source.components().count())
+    }
+
+    /// インストール先の`path`を含む対応．複数ある場合は`dest`が最も深いもの．
+    /// (例: `~/.local/bin`への対応は$HOMEへの対応より優先する)
+    pub fn target_of_dest(&self, path: impl AsRef<Path>) -> Option<&Target> {
+        let path = path.as_ref();
+        self.targets
+            .iter()
+            .filter(|target| path.starts_with(&target.dest))
+            .max_by_key(|target| target.dest.components().count())
+    }
+
+    /// 対応する`source`からの相対パス(例: ~/.dotfiles/home/foo/bar -> foo/bar)
+    pub fn repo_rel_from_dotfiles_home(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
+        let path = path.as_ref();
+        match self.target_of_source(path) {
+            Some(target) => strip_prefix(path, &target.source),
+            None => Err(Error::OutOfScope {
+                path: path.to_path_buf(),
+                scope: self.dotfiles_dir.clone(),
+            }),
+        }
+    }
+
+    pub fn repo_rel_from_home(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
+        strip_prefix(path.as_ref(), &self.home_dir)
+    }
+
+    /// レポジトリ内の`path`を対応する`dest`に"インストール"した場合の絶対パス
+    /// 暗号化されたファイルの場合は接尾辞`.enc`を除く．
+    pub fn install_path(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
+        let path = path.as_ref();
+        let Some(Target { source, dest }) = self.target_of_source(path) else {
+            return Err(Error::OutOfScope {
+                path: path.to_path_buf(),
+                scope: self.dotfiles_dir.clone(),
+            });
+        };
+        let rel = strip_prefix(path, source)?;
+
+        match rel
+            .to_str()
+            .and_then(|rel| rel.strip_suffix(SECRET_SUFFIX))
+            .filter(|rel| !rel.is_empty() && !rel.ends_with('/'))
+        {
+            Some(stripped) => Ok(dest.join(stripped)),
+            None => Ok(dest.join(rel)),
+        }
+    }
+
+    /// レポジトリ内の`path`が暗号化されたファイルならtrue．
+    pub fn is_secret(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+
+        path.to_str()
+            .is_some_and(|path| path.ends_with(SECRET_SUFFIX))
+            || self
+                .repo_rel_from_dotfiles_home(path)
+                .is_ok_and(|rel| self.secrets.files.contains(&rel))
+    }
+
+    /// インストール先の`path`に対応するdotfilesレポジトリ内の絶対パス
+    pub fn entity_path(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
+        let path = path.as_ref();
+        match self.target_of_dest(path) {
+            Some(target) => Ok(target.source.join(strip_prefix(path, &target.dest)?)),
+            None => Err(Error::OutOfScope {
+                path: path.to_path_buf(),
+                scope: self.home_dir.clone(),
+            }),
+        }
+    }
+}
+
+/// `key`(`link.0.src`のようなserde_ignoredのパス)を定義したファイル．
+/// `key`自身に無ければ親のテーブル，それも無ければ`key`以下の値のもの．
+fn origin_of_key<'a>(layered: &'a LayeredConfig, key: &str) -> Option<&'a Path> {
+    let mut prefix = key;
+    loop {
+        if let Some(origin) = layered.origin(prefix) {
+            return Some(origin);
+        }
+        match prefix.rfind('.') {
+            Some(end) => prefix = &prefix[..end],
+            None => break,
+        }
+    }
+
+    let nested = format!("{}.", key);
+    layered
+        .origins
+        .iter()
+        .find(|(origin_key, _)| origin_key.starts_with(&nested))
+        .map(|(_, origin)| origin.as_path())
+}
+
+fn strip_prefix(path: &Path, scope: &Path) -> Result<PathBuf> {
+    path.strip_prefix(scope)
+        .map(Path::to_path_buf)
+        .map_err(|_| Error::OutOfScope {
+            path: path.to_path_buf(),
+            scope: scope.to_path_buf(),
+        })
+}
+
+/// 設定ファイル中のパス`raw`の`~`，`$VAR`，`${VAR}`を展開する．
+/// `key`はエラー表示用の設定項目名(環境変数の値を展開する場合はその環境変数名)．
+pub fn expand_path(key: &'static str, raw: &str) -> Result<PathBuf> {
+    expand_path_with(key, raw, |name| env::var(name).ok())
+}
+
+/// `expand_path`の環境変数の値を`var`で得るもの．
+fn expand_path_with(
+    key: &'static str,
+    raw: &str,
+    var: impl Fn(&str) -> Option<String>,
+) -> Result<PathBuf> {
+    let path = match raw.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("${{HOME}}{}", rest),
+        _ => raw.to_string(),
+    };
+
+    let mut expanded = String::new();
+    let mut rest = path.as_str();
+
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        let (name, after) = match rest.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => {
+                    return Err(Error::UnterminatedVariable {
+                        key,
+                        raw: raw.to_string(),
+                    });
+                }
+            },
+            None => {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                (&rest[..end], &rest[end..])
+            }
+        };
+
+        if name.is_empty() {
+            expanded.push('$');
+            continue;
+        }
+
+        let value = var_or_xdg_default(name, &var).ok_or_else(|| Error::UndefinedVariable {
+            key,
+            name: name.to_string(),
+        })?;
+        expanded.push_str(&value);
+        rest = after;
+    }
+    expanded.push_str(rest);
+
+    Ok(PathBuf::from(expanded))
+}
+
+/// 環境変数`name`の値．
+/// XDG Base Directoryの変数が未設定の場合は仕様上の既定値(`$HOME/.config`など)を返す．
+fn var_or_xdg_default(name: &str, var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    if let Some(value) = var(name).filter(|value| !value.is_empty()) {
+        return Some(value);
+    }
+
+    let default = match name {
+        "XDG_CONFIG_HOME" => ".config",
+        "XDG_DATA_HOME" => ".local/share",
+        "XDG_STATE_HOME" => ".local/state",
+        "XDG_CACHE_HOME" => ".cache",
+        _ => return None,
+    };
+    let home = var("HOME").filter(|home| !home.is_empty())?;
+    Some(format!("{}/{}", home, default))
+}
+
+/// `path`の親ディレクトリを正規化する．`path`自身はシンボリックリンクのことがあるので辿らない．
+/// 親ディレクトリがまだ存在しなければそのまま返す．
+fn canonicalize_parent(path: PathBuf) -> PathBuf {
+    let Some(name) = path.file_name() else {
+        return path;
+    };
+    match path.parent().map(Path::canonicalize) {
+        Some(Ok(parent)) => parent.join(name),
+        _ => path,
+    }
+}
+
+/// 設定ファイル中のディレクトリ`path`を絶対パスにする．
+/// `key`はエラー表示用の設定項目名．
+fn canonicalize_dir(key: &'static str, path: PathBuf) -> Result<PathBuf> {
+    let path = path
+        .canonicalize()
+        .map_err(|source| Error::InvalidConfigPath { key, path, source })?;
+
+    if !path.is_dir() {
+        return Err(Error::NotADirectory { path });
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, os::unix};
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// `root/dotfiles/home`，`root/home`，`root/backup`と，
+    /// それらを相対パスで指す`root/conf/dotkoke_config.toml`をつくる．
+    fn relative_config() -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        for sub in ["dotfiles/home", "home", "conf/backup"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        fs::write(
+            root.join("conf/dotkoke_config.toml"),
+            "version = 1\n\n[general]\ndotfiles = \"../dotfiles\"\nhome = \"../home\"\nbackup_dir = \"backup\"\n",
+        )
+        .unwrap();
+        (dir, root)
+    }
+
+    #[test]
+    fn relative_paths_are_resolved_from_config_dir() {
+        let (_dir, root) = relative_config();
+
+        let config = Config::read(root.join("conf/dotkoke_config.toml")).unwrap();
+
+        assert_eq!(config.dotfiles_dir, root.join("dotfiles"));
+        assert_eq!(config.dotfiles_home_dir, root.join("dotfiles/home"));
+        assert_eq!(config.home_dir, root.join("home"));
+        assert_eq!(
+            config.backup_dir.parent(),
+            Some(root.join("conf/backup").as_path())
+        );
+    }
+
+    #[test]
+    fn relative_paths_in_include_are_resolved_from_included_file() {
+        let (_dir, root) = relative_config();
+        fs::create_dir_all(root.join("other/home")).unwrap();
+        fs::write(
+            root.join("other/common.toml"),
+            "version = 1\n\n[general]\nhome = \"home\"\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("main.toml"),
+            "version = 1\ninclude = \"other/common.toml\"\n\n[general]\ndotfiles = \"dotfiles\"\n",
+        )
+        .unwrap();
+
+        let config = Config::read(root.join("main.toml")).unwrap();
+
+        assert_eq!(config.dotfiles_dir, root.join("dotfiles"));
+        assert_eq!(config.home_dir, root.join("other/home"));
+    }
+
+    #[test]
+    fn relative_paths_in_symlinked_config_are_resolved_from_link_target() {
+        let (_dir, root) = relative_config();
+        fs::create_dir_all(root.join("xdg/dotkoke")).unwrap();
+        unix::fs::symlink(
+            root.join("conf/dotkoke_config.toml"),
+            root.join("xdg/dotkoke/config.toml"),
+        )
+        .unwrap();
+
+        let config = Config::read(root.join("xdg/dotkoke/config.toml")).unwrap();
+
+        // リンク先の`conf/`からの相対パスになる．
+        assert_eq!(config.dotfiles_dir, root.join("dotfiles"));
+        assert_eq!(config.home_dir, root.join("home"));
+        assert_eq!(
+            config.backup_dir.parent(),
+            Some(root.join("conf/backup").as_path())
+        );
+    }
+
+    #[test]
+    fn missing_relative_dir_is_error() {
+        let (_dir, root) = relative_config();
+        fs::remove_dir(root.join("home")).unwrap();
+
+        assert!(matches!(
+            Config::read(root.join("conf/dotkoke_config.toml")),
+            Err(Error::InvalidConfigPath { key: "home", .. })
+        ));
+    }
+
+    fn var(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/user".to_string()),
+            "DOTFILES" => Some("/srv/dotfiles".to_string()),
+            "XDG_CONFIG_HOME" => Some("/etc/user-config".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn expand(raw: &str) -> Result<PathBuf> {
+        expand_path_with("general.dotfiles", raw, var)
+    }
+
+    #[test]
+    fn expand_path_expands_tilde_and_variables() {
+        assert_eq!(expand("~").unwrap(), PathBuf::from("/home/user"));
+        assert_eq!(expand("~/x").unwrap(), PathBuf::from("/home/user/x"));
+        // `~user`や途中の`~`は展開しない．
+        assert_eq!(expand("~other/x").unwrap(), PathBuf::from("~other/x"));
+        assert_eq!(expand("a/~/b").unwrap(), PathBuf::from("a/~/b"));
+        assert_eq!(
+            expand("$DOTFILES/home").unwrap(),
+            PathBuf::from("/srv/dotfiles/home")
+        );
+        assert_eq!(
+            expand("${DOTFILES}-old").unwrap(),
+            PathBuf::from("/srv/dotfiles-old")
+        );
+        assert_eq!(expand("a$/b").unwrap(), PathBuf::from("a$/b"));
+    }
+
+    #[test]
+    fn expand_path_uses_xdg_defaults() {
+        assert_eq!(
+            expand("$XDG_CONFIG_HOME/dotfiles").unwrap(),
+            PathBuf::from("/etc/user-config/dotfiles")
+        );
+        assert_eq!(
+            expand("${XDG_DATA_HOME}/dotfiles").unwrap(),
+            PathBuf::from("/home/user/.local/share/dotfiles")
+        );
+        assert_eq!(
+            expand("$XDG_STATE_HOME").unwrap(),
+            PathBuf::from("/home/user/.local/state")
+        );
+        assert_eq!(
+            expand("$XDG_CACHE_HOME").unwrap(),
+            PathBuf::from("/home/user/.cache")
+        );
+    }
+
+    #[test]
+    fn expand_path_rejects_undefined_and_unterminated_variables() {
+        // 空の値は未設定と同様に扱う．
+        for (raw, undefined) in [("$UNDEFINED/x", "UNDEFINED"), ("${EMPTY}/x", "EMPTY")] {
+            match expand(raw) {
+                Err(Error::UndefinedVariable { key, name }) => {
+                    assert_eq!(key, "general.dotfiles");
+                    assert_eq!(name, undefined);
+                }
+                other => panic!("unexpected result for {}: {:?}", raw, other),
+            }
+        }
+
+        match expand("~/${DOTFILES/x") {
+            Err(error @ Error::UnterminatedVariable { .. }) => assert_eq!(
+                error.to_string(),
+                "general.dotfiles has unterminated ${ in \"~/${DOTFILES/x\""
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn undefined_variable_in_config_is_error() {
+        let (_dir, root) = relative_config();
+        fs::write(
+            root.join("conf/dotkoke_config.toml"),
+            "version = 1\n\n[general]\ndotfiles = \"$DOTKOKE_TEST_UNDEFINED/dotfiles\"\n",
+        )
+        .unwrap();
+
+        match Config::read(root.join("conf/dotkoke_config.toml")) {
+            Err(error @ Error::UndefinedVariable { .. }) => assert_eq!(
+                error.to_string(),
+                "dotfiles refers to undefined environment variable $DOTKOKE_TEST_UNDEFINED"
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! 設定したディレクトリ同士の関係の検査．
+
+use std::{
+    fs,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+use crate::config::Config;
+
+/// `Config::check`の結果．
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigCheck {
+    // 使用すると壊れる設定．
+    pub errors: Vec<String>,
+
+    // 動作はするが疑わしい設定．
+    pub warnings: Vec<String>,
+}
+
+impl Config {
+    /// ディレクトリが入れ子・重複していないか調べる．
+    pub fn check(&self) -> ConfigCheck {
+        let mut check = ConfigCheck::default();
+
+        // `backup_dir`は`YYYYmmdd_HHMM`を含むので，その親が設定した値．
+        let backup_root = self.backup_dir.parent().unwrap_or(&self.backup_dir);
+
+        // $HOMEがdotfilesレポジトリ内にあると，リンクがレポジトリ内に作られる．
+        if self.home_dir.starts_with(&self.dotfiles_dir) {
+            check.errors.push(format!(
+                "home {} is inside dotfiles {}.",
+                self.home_dir.display(),
+                self.dotfiles_dir.display()
+            ));
+        }
+
+        // インストール先がミラー内にあると，インストールするたびにミラーが入れ子になる．
+        for target in &self.targets {
+            for source in self.sources_containing(&target.dest) {
+                check.errors.push(format!(
+                    "install destination {} is inside {}.",
+                    target.dest.display(),
+                    source.display()
+                ));
+            }
+        }
+
+        for link in &self.links {
+            for source in self.sources_containing(&link.dst) {
+                check.errors.push(format!(
+                    "link destination {} is inside {}.",
+                    link.dst.display(),
+                    source.display()
+                ));
+            }
+        }
+
+        // 退避したファイルがミラー内にあると，$HOMEにリンクされたりコミットされたりする．
+        if backup_root.starts_with(&self.dotfiles_dir) {
+            check.errors.push(format!(
+                "backup_dir {} is inside dotfiles {}.",
+                backup_root.display(),
+                self.dotfiles_dir.display()
+            ));
+        } else if self.dotfiles_dir.starts_with(backup_root) {
+            check.errors.push(format!(
+                "dotfiles {} is inside backup_dir {}.",
+                self.dotfiles_dir.display(),
+                backup_root.display()
+            ));
+        }
+
+        for (i, target) in self.targets.iter().enumerate() {
+            if self.targets[..i]
+                .iter()
+                .any(|other| other.source == target.source)
+            {
+                check.warnings.push(format!(
+                    "{} is used as source of multiple targets.",
+                    target.source.display()
+                ));
+            }
+        }
+
+        // `dest`が別の`dest`の中にあり，外側のミラーにも対応するパスがあると，
+        // 同じインストール先に2つのファイルが対応してしまう．
+        // (例: dotfiles/home/.config/と，`dest`が~/.configの`[[target]]`)
+        for (i, inner) in self.targets.iter().enumerate() {
+            for (j, outer) in self.targets.iter().enumerate() {
+                // `dest`が同じ組は1度だけ報告する．
+                if i == j || (inner.dest == outer.dest && j > i) {
+                    continue;
+                }
+                let Ok(rel) = inner.dest.strip_prefix(&outer.dest) else {
+                    continue;
+                };
+                let shadowed = outer.source.join(rel);
+                if shadowed.symlink_metadata().is_ok() {
+                    check.errors.push(format!(
+                        "install destination {} is reachable from both {} and {}.",
+                        inner.dest.display(),
+                        shadowed.display(),
+                        inner.source.display()
+                    ));
+                }
+            }
+        }
+
+        // 退避はrenameで行うので，ファイルシステムをまたぐと失敗する．
+        let mut dests = self
+            .targets
+            .iter()
+            .map(|target| target.dest.as_path())
+            .chain([self.home_dir.as_path()])
+            .collect::<Vec<_>>();
+        dests.sort_unstable();
+        dests.dedup();
+        if let Some(backup_dev) = device_of(backup_root) {
+            for dest in dests {
+                if device_of(dest).is_some_and(|dev| dev != backup_dev) {
+                    check.warnings.push(format!(
+                        "backup_dir {} is on a different filesystem from {}. backups will fail.",
+                        backup_root.display(),
+                        dest.display()
+                    ));
+                }
+            }
+        }
+
+        check
+    }
+
+    /// `path`を含むミラー(`[[target]]`の`source`)．
+    fn sources_containing(&self, path: &Path) -> Vec<&PathBuf> {
+        self.targets
+            .iter()
+            .map(|target| &target.source)
+            .filter(|source| path.starts_with(source))
+            .collect()
+    }
+}
+
+/// `path`のあるファイルシステムのデバイス番号．
+/// `path`がまだ存在しなければ，存在する最も近い親ディレクトリのもの．
+fn device_of(path: &Path) -> Option<u64> {
+    path.ancestors()
+        .find_map(|path| fs::metadata(path).ok())
+        .map(|meta| meta.dev())
+}
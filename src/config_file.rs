@@ -0,0 +1,183 @@
+//! 設定ファイルの読み込みと重ね合わせ．
+//!
+//! 1. 指定された設定ファイルを読み込む．
+//!    `include`(`~`，環境変数は展開する)で指定したファイルがあれば先に読み込み，それを設定ファイル自身の値で上書きする．
+//! 1. 設定ファイルと同じディレクトリに`<名前>.local.toml`(例: `dotkoke_config.local.toml`)があれば，
+//!    同様に読み込んで上書きする．
+//!
+//! 各ファイルは重ね合わせる前に現在の形式に移行する(`config_migration`を参照)．
+//! テーブルはキーごとに上書きし，それ以外の値(配列を含む)は丸ごと置き換える．
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value};
+
+use crate::{
+    config::expand_path,
+    config_migration::{CONFIG_VERSION, migrate},
+    error::{Error, Result},
+};
+
+/// 重ね合わせた設定ファイルの内容．
+#[derive(Debug, Clone, Default)]
+pub struct LayeredConfig {
+    // 読み込んだファイル．後のものほど優先される．
+    pub files: Vec<PathBuf>,
+
+    // 重ね合わせた内容(`include`を除く)．
+    pub table: Table,
+
+    // 値のキー(`general.dotfiles`のようなドット区切り) -> その値を定義したファイル
+    pub origins: BTreeMap<String, PathBuf>,
+
+    // 古い形式のファイルがあった場合の警告．
+    pub warnings: Vec<String>,
+}
+
+impl LayeredConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = canonicalize_file(path.as_ref())?;
+
+        let mut layered = LayeredConfig::default();
+        layered.load_file(&path, &mut vec![])?;
+
+        if let Some(local) = local_path(&path).filter(|local| local.is_file()) {
+            let local = canonicalize_file(&local)?;
+            if !layered.files.contains(&local) {
+                layered.load_file(&local, &mut vec![])?;
+            }
+        }
+
+        Ok(layered)
+    }
+
+    /// `key`の値を定義したファイル．
+    pub fn origin(&self, key: &str) -> Option<&Path> {
+        self.origins.get(key).map(PathBuf::as_path)
+    }
+
+    /// `path`を，`include`するファイルの後に読み込んで重ね合わせる．
+    /// `visiting`は`include`の循環を検出するための，読み込み中のファイル一覧．
+    fn load_file(&mut self, path: &Path, visiting: &mut Vec<PathBuf>) -> Result<()> {
+        if visiting.iter().any(|visited| visited == path) {
+            return Err(Error::ConfigIncludeCycle {
+                path: path.to_path_buf(),
+            });
+        }
+
+        let toml_str = fs::read_to_string(path).map_err(|source| Error::ConfigRead {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let mut table = toml_str
+            .parse::<Table>()
+            .map_err(|source| Error::ConfigParse {
+                path: path.to_path_buf(),
+                source,
+            })?;
+
+        let includes = match table.remove("include") {
+            None => vec![],
+            Some(Value::String(include)) => vec![include],
+            Some(Value::Array(includes)) => includes
+                .into_iter()
+                .map(|include| match include {
+                    Value::String(include) => Ok(include),
+                    _ => Err(Error::InvalidInclude {
+                        path: path.to_path_buf(),
+                    }),
+                })
+                .collect::<Result<_>>()?,
+            Some(_) => {
+                return Err(Error::InvalidInclude {
+                    path: path.to_path_buf(),
+                });
+            }
+        };
+
+        // 各ファイルを重ね合わせる前に現在の形式にする．
+        // `migrate`が加えた`version`はファイルに書かれた値ではないので，由来を記録しないよう取り除く．
+        let has_version = table.contains_key("version");
+        let version = migrate(path, &mut table)?;
+        if !has_version {
+            table.remove("version");
+        }
+        if version < CONFIG_VERSION {
+            self.warnings.push(format!(
+                "{} is config version {}. run `dotkoke config migrate` to update it to version {}.",
+                path.display(),
+                version,
+                CONFIG_VERSION
+            ));
+        }
+
+        visiting.push(path.to_path_buf());
+        let dir = path.parent().expect("config file should have parent");
+        for include in includes {
+            let include = canonicalize_file(&dir.join(expand_path("include", &include)?))?;
+            self.load_file(&include, visiting)?;
+        }
+        visiting.pop();
+
+        merge(&mut self.table, table, "", path, &mut self.origins);
+        self.files.push(path.to_path_buf());
+
+        Ok(())
+    }
+}
+
+/// `dotkoke_config.toml` -> `dotkoke_config.local.toml`
+fn local_path(path: &Path) -> Option<PathBuf> {
+    let stem = path.file_stem()?.to_str()?;
+    if stem.ends_with(".local") {
+        return None;
+    }
+    Some(path.with_file_name(format!("{}.local.toml", stem)))
+}
+
+fn canonicalize_file(path: &Path) -> Result<PathBuf> {
+    path.canonicalize().map_err(|source| Error::ConfigRead {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// `src`を`dst`に重ね合わせ，上書きした値の定義元を`origin`にする．
+/// `prefix`は`dst`のキー(トップレベルなら空)．
+fn merge(
+    dst: &mut Table,
+    src: Table,
+    prefix: &str,
+    origin: &Path,
+    origins: &mut BTreeMap<String, PathBuf>,
+) {
+    for (key, value) in src {
+        let full_key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match (dst.get_mut(&key), value) {
+            (Some(Value::Table(dst)), Value::Table(src)) => {
+                merge(dst, src, &full_key, origin, origins);
+            }
+            (_, Value::Table(src)) => {
+                let mut table = Table::new();
+                merge(&mut table, src, &full_key, origin, origins);
+                dst.insert(key, Value::Table(table));
+            }
+            (_, value) => {
+                let nested = format!("{}.", full_key);
+                origins.retain(|key, _| !key.starts_with(&nested));
+                origins.insert(full_key, origin.to_path_buf());
+                dst.insert(key, value);
+            }
+        }
+    }
+}
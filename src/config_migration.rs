@@ -0,0 +1,146 @@
+//! 設定ファイルの形式のバージョンと，古い形式からの移行．
+//!
+//! 設定ファイルはトップレベルの`version`で形式のバージョンを表す．
+//! `version`が無いものはバージョン0とする．
+//! 読み込み時はメモリ上で現在の形式に移行し，`config migrate`でファイルを書き換える．
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value};
+
+use crate::{
+    config_file::LayeredConfig,
+    error::{Error, Result},
+    file_operations::write_file,
+};
+
+/// 現在の設定ファイルの形式のバージョン．
+pub const CONFIG_VERSION: u32 = 1;
+
+/// `version`が無い設定ファイルのバージョン．
+const UNVERSIONED: u32 = 0;
+
+/// 移行処理．`MIGRATIONS[n]`はバージョン`n`から`n + 1`に移行する．
+const MIGRATIONS: [fn(&mut Table); CONFIG_VERSION as usize] = [migrate_v0];
+
+/// バージョン0(`version`導入前)とバージョン1は`version`の有無のみ異なる．
+fn migrate_v0(_: &mut Table) {}
+
+/// `path`から読み込んだ`table`を現在の形式に移行し，移行前のバージョンを返す．
+pub(crate) fn migrate(path: &Path, table: &mut Table) -> Result<u32> {
+    let version = match table.get("version") {
+        None => UNVERSIONED,
+        Some(Value::Integer(version)) => {
+            u32::try_from(*version).map_err(|_| Error::InvalidConfigVersion {
+                path: path.to_path_buf(),
+            })?
+        }
+        Some(_) => {
+            return Err(Error::InvalidConfigVersion {
+                path: path.to_path_buf(),
+            });
+        }
+    };
+
+    if version > CONFIG_VERSION {
+        return Err(Error::UnsupportedConfigVersion {
+            path: path.to_path_buf(),
+            version,
+        });
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(table);
+    }
+    table.insert("version".to_string(), Value::Integer(CONFIG_VERSION.into()));
+
+    Ok(version)
+}
+
+/// `config migrate`で移行した(する)設定ファイル．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigMigration {
+    pub path: PathBuf,
+
+    // 移行前のバージョン．
+    pub from: u32,
+
+    // 移行後の内容．
+    pub content: String,
+
+    // 移行前の内容を保存したファイル(`<path>.bak`)．
+    pub backup: PathBuf,
+}
+
+/// `config_toml_path`と，そこから読み込まれる設定ファイル(`include`，`*.local.toml`)のうち
+/// 古い形式のものを現在の形式で書き換える．移行前の内容は`<path>.bak`に保存する．
+/// 既に`<path>.bak`がある場合は，何も書き換えずにエラーにする．
+///
+/// `version`の追加以外の変更がある場合はtomlとして書き直すので，コメントや書式は保持されない．
+pub fn migrate_config(
+    config_toml_path: impl AsRef<Path>,
+    dry_run: bool,
+) -> Result<Vec<ConfigMigration>> {
+    let layered = LayeredConfig::load(config_toml_path)?;
+
+    let mut migrations = vec![];
+
+    // 各`migrations`の移行前の内容．
+    let mut originals = vec![];
+
+    for path in layered.files {
+        let toml_str = fs::read_to_string(&path).map_err(|source| Error::ConfigRead {
+            path: path.clone(),
+            source,
+        })?;
+        let mut table = toml_str
+            .parse::<Table>()
+            .map_err(|source| Error::ConfigParse {
+                path: path.clone(),
+                source,
+            })?;
+
+        let original = table.clone();
+        let from = migrate(&path, &mut table)?;
+        if from == CONFIG_VERSION {
+            continue;
+        }
+
+        // `version`を書き加えるだけで済む場合は，コメントや書式を保持する．
+        let mut unversioned = table.clone();
+        unversioned.remove("version");
+        let content = if unversioned == original {
+            format!("version = {}\n\n{}", CONFIG_VERSION, toml_str)
+        } else {
+            toml::to_string(&table).expect("config table should be serializable")
+        };
+
+        let mut backup = path.clone().into_os_string();
+        backup.push(".bak");
+        let backup = PathBuf::from(backup);
+        if backup.symlink_metadata().is_ok() {
+            return Err(Error::MigrationBackupExists { path: backup });
+        }
+
+        migrations.push(ConfigMigration {
+            path,
+            from,
+            content,
+            backup,
+        });
+        originals.push(toml_str);
+    }
+
+    // 一部のファイルのみ書き換えることが無いよう，すべての移行内容を確かめてから書き込む．
+    if !dry_run {
+        for (migration, original) in migrations.iter().zip(&originals) {
+            write_file(&migration.backup, original.as_bytes())?;
+            write_file(&migration.path, migration.content.as_bytes())?;
+        }
+    }
+
+    Ok(migrations)
+}
@@ -0,0 +1,234 @@
+//! ライブラリが返すエラー．
+
+use std::{io, path::PathBuf};
+
+use crate::{file_kind::FileKind, secret_scan::SecretFinding};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to read config file: {}", path.display())]
+    ConfigRead {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("failed to parse config file: {}", path.display())]
+    ConfigParse {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    #[error("include in {} must be a string or an array of strings", path.display())]
+    InvalidInclude { path: PathBuf },
+
+    #[error("version in {} must be a non-negative integer", path.display())]
+    InvalidConfigVersion { path: PathBuf },
+
+    #[error(
+        "{} is config version {version}, but this dotkoke supports up to version {}.",
+        path.display(),
+        crate::config_migration::CONFIG_VERSION
+    )]
+    UnsupportedConfigVersion { path: PathBuf, version: u32 },
+
+    #[error("{} already exists. move or remove it before migrating.", path.display())]
+    MigrationBackupExists { path: PathBuf },
+
+    #[error("{} includes itself.", path.display())]
+    ConfigIncludeCycle { path: PathBuf },
+
+    #[error(
+        "dotfiles directory is not configured. set [general] dotfiles, --dotfiles or DOTKOKE_DOTFILES."
+    )]
+    MissingDotfilesDir,
+
+    /// `Config::check`でエラーになった．
+    #[error(
+        "invalid directory layout in config:{}",
+        problems.iter().map(|problem| format!("\n  {}", problem)).collect::<String>()
+    )]
+    InvalidDirectoryLayout { problems: Vec<String> },
+
+    #[error("invalid {key} directory in config: {}", path.display())]
+    InvalidConfigPath {
+        key: &'static str,
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error(
+        "invalid mode for {pattern} in [permissions]: {mode:?} (expected octal such as \"0700\")"
+    )]
+    InvalidMode { pattern: String, mode: String },
+
+    #[error("{key} refers to undefined environment variable ${name}")]
+    UndefinedVariable { key: &'static str, name: String },
+
+    #[error("{key} has unterminated ${{ in {raw:?}")]
+    UnterminatedVariable { key: &'static str, raw: String },
+
+    #[error("{} is not directory.", path.display())]
+    NotADirectory { path: PathBuf },
+
+    /// `path`が対象とすべきディレクトリ(`scope`)の外にある．
+    #[error("{} is not in {}.", path.display(), scope.display())]
+    OutOfScope { path: PathBuf, scope: PathBuf },
+
+    /// `path`が既にdotfiles管理下にある．
+    #[error("{} is in {}.", path.display(), dotfiles_home_dir.display())]
+    AlreadyManaged {
+        path: PathBuf,
+        dotfiles_home_dir: PathBuf,
+    },
+
+    #[error("{} is not {}.", path.display(), expected)]
+    UnexpectedFileKind { path: PathBuf, expected: FileKind },
+
+    /// 計画作成時からファイルの状態が変わっている．
+    #[error(
+        "file state has changed since the plan was made:{}",
+        paths.iter().map(|path| format!("\n  {}", path.display())).collect::<String>()
+    )]
+    Conflict { paths: Vec<PathBuf> },
+
+    /// 計画と現在の設定でディレクトリが異なる．
+    #[error(
+        "{key} directory mismatch: plan is for {}, but config is {}.",
+        planned.display(),
+        configured.display()
+    )]
+    PlanMismatch {
+        key: &'static str,
+        planned: PathBuf,
+        configured: PathBuf,
+    },
+
+    #[error("failed to parse plan: {}", path.display())]
+    PlanParse {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("failed to serialize plan: {}", path.display())]
+    PlanSerialize {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("git {args} failed in {}: {stderr}", repo.display())]
+    Git {
+        repo: PathBuf,
+        args: String,
+        stderr: String,
+    },
+
+    #[error("{} is not a git repository. disable [git] auto_commit or run `git init`.", repo.display())]
+    NotAGitRepo { repo: PathBuf },
+
+    #[error(
+        "{} has uncommitted changes. commit or stash them first.\n{status}",
+        repo.display()
+    )]
+    DirtyRepo { repo: PathBuf, status: String },
+
+    #[error(
+        "{} has diverged from its upstream. merge or rebase it first.",
+        repo.display()
+    )]
+    DivergedRepo { repo: PathBuf },
+
+    #[error("secrets.{key} is not configured.")]
+    SecretCommandNotConfigured { key: &'static str },
+
+    #[error("`{command}` failed for {}: {stderr}", path.display())]
+    SecretCommand {
+        command: String,
+        path: PathBuf,
+        stderr: String,
+    },
+
+    /// 暗号化せずに加えようとしたファイルに秘密情報らしきものがある．
+    #[error(
+        "{} seems to contain secrets:{}\nuse --encrypt, or --allow-secrets to add it as is.",
+        path.display(),
+        findings.iter().map(|finding| format!("\n  line {}: {}", finding.line, finding.kind)).collect::<String>()
+    )]
+    SecretDetected {
+        path: PathBuf,
+        findings: Vec<SecretFinding>,
+    },
+
+    #[error("{hook} hook `{command}` failed: {status}")]
+    Hook {
+        hook: &'static str,
+        command: String,
+        status: String,
+    },
+
+    #[error("hook {} is not executable. make it executable or remove it.", path.display())]
+    HookNotExecutable { path: PathBuf },
+
+    #[error("script {} failed: {status}", path.display())]
+    Script { path: PathBuf, status: String },
+
+    #[error("script {} has non-UTF-8 name.", path.display())]
+    InvalidScriptName { path: PathBuf },
+
+    #[error("failed to parse state file: {}", path.display())]
+    StateParse {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("trigger `{command}` failed: {status}")]
+    Trigger { command: String, status: String },
+
+    #[error(
+        "failed to {action}: {}{}",
+        path.display(),
+        dest.as_ref().map(|dest| format!(" -> {}", dest.display())).unwrap_or_default()
+    )]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+
+        // rename，copyなど2つのパスを扱う操作の場合の操作先．
+        dest: Option<PathBuf>,
+
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl Error {
+    pub(crate) fn io(action: &'static str, path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Error::Io {
+            action,
+            path: path.into(),
+            dest: None,
+            source,
+        }
+    }
+
+    pub(crate) fn io_pair(
+        action: &'static str,
+        path: impl Into<PathBuf>,
+        dest: impl Into<PathBuf>,
+        source: io::Error,
+    ) -> Self {
+        Error::Io {
+            action,
+            path: path.into(),
+            dest: Some(dest.into()),
+            source,
+        }
+    }
+}
@@ -1,9 +1,8 @@
-use std::path::Path;
-
-use anyhow::Result;
+use std::path::{Path, PathBuf};
 
 use crate::{
     config::Config,
+    error::Result,
     executor::{Executor, HasConfig},
 };
 
@@ -23,50 +22,66 @@ impl HasConfig for DryExecutor {
     }
 }
 
+// 何も操作しない．
+// 処理予定内容は各コマンドの返すreportから表示する．
 impl Executor for DryExecutor {
-    fn create_symlink(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
-        println!(
-            "[dry-run] ln -s {} -> {}",
-            from.as_ref().display(),
-            to.as_ref().display()
-        );
+    fn create_symlink(&self, _from: impl AsRef<Path>, _to: impl AsRef<Path>) -> Result<()> {
+        Ok(())
+    }
+
+    fn remove_symlink_from_home(&self, _path: impl AsRef<Path>) -> Result<()> {
+        Ok(())
+    }
+
+    fn remove_file_from_home(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
+        self.backup_path(path)
+    }
+
+    fn remove_dir_all_from_home(&self, _path: impl AsRef<Path>) -> Result<()> {
+        Ok(())
+    }
+
+    fn remove_unknown_path_from_home(&self, _path: impl AsRef<Path>) -> Result<()> {
+        Ok(())
+    }
+
+    fn remove_file_from_dotfiles_home(&self, _path: impl AsRef<Path>) -> Result<()> {
+        Ok(())
+    }
+
+    fn copy(&self, _from: impl AsRef<Path>, _to: impl AsRef<Path>) -> Result<()> {
+        Ok(())
+    }
+
+    fn decrypt_to_home(&self, _from: impl AsRef<Path>, _to: impl AsRef<Path>) -> Result<()> {
         Ok(())
     }
 
-    fn remove_symlink_from_home(&self, path: impl AsRef<Path>) -> Result<()> {
-        println!("[dry-run] rm (symlink) {}", path.as_ref().display());
+    fn encrypt_to_dotfiles_home(
+        &self,
+        _from: impl AsRef<Path>,
+        _to: impl AsRef<Path>,
+    ) -> Result<()> {
         Ok(())
     }
 
-    fn remove_file_from_home(&self, path: impl AsRef<Path>) -> Result<()> {
-        let path = path.as_ref();
-        let suffix = path.strip_prefix(self.home_dir())?;
-        let backup = self.backup_dir().join(suffix);
-        println!("[dry-run] mv {} -> {}", path.display(), backup.display());
+    fn set_mode(&self, _path: impl AsRef<Path>, _mode: u32) -> Result<()> {
         Ok(())
     }
 
-    fn remove_dir_all_from_home(&self, path: impl AsRef<Path>) -> Result<()> {
-        println!("[dry-run] rm -rf {}", path.as_ref().display());
+    fn run_script(&self, _script: impl AsRef<Path>) -> Result<()> {
         Ok(())
     }
 
-    fn remove_unknown_path_from_home(&self, path: impl AsRef<Path>) -> Result<()> {
-        println!("[dry-run] unlink (unknown) {}", path.as_ref().display());
+    fn run_trigger(&self, _command: &str, _paths: &[PathBuf]) -> Result<()> {
         Ok(())
     }
 
-    fn remove_file_from_dotfiles_home(&self, path: impl AsRef<Path>) -> Result<()> {
-        println!("[dry-run] rm -rf {}", path.as_ref().display());
+    fn git_commit(&self, _paths: &[PathBuf], _message: &str) -> Result<()> {
         Ok(())
     }
 
-    fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
-        println!(
-            "[dry-run] cp {} -> {}",
-            from.as_ref().display(),
-            to.as_ref().display()
-        );
+    fn git_fast_forward(&self) -> Result<()> {
         Ok(())
     }
 }
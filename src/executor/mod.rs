@@ -1,13 +1,21 @@
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use crate::{config::Config, error::Result};
 
-use crate::config::Config;
-
-trait HasConfig {
+pub(crate) trait HasConfig {
     fn config(&self) -> &Config;
 }
 
+/// `Executor::back_up_file_from_home`の結果．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    // 退避先のパス．
+    pub path: PathBuf,
+
+    // 退避のために作成したディレクトリ(存在しなかったbackup_dir)．
+    pub created_dir: Option<PathBuf>,
+}
+
 #[allow(private_bounds)]
 pub trait Executor: HasConfig {
     fn home_dir(&self) -> &Path {
@@ -19,50 +27,76 @@ pub trait Executor: HasConfig {
     fn backup_dir(&self) -> &Path {
         &self.config().backup_dir
     }
+    fn dotfiles_dir(&self) -> &Path {
+        &self.config().dotfiles_dir
+    }
+    fn auto_commit(&self) -> bool {
+        self.config().git.auto_commit
+    }
 
-    /// dotfiles レポジトリからの相対パス(例: ~/.dotfiles/foo/bar -> foo/bar)
+    /// 対応する`source`からの相対パス(例: ~/.dotfiles/home/foo/bar -> foo/bar)
     fn repo_rel_from_dotfiles_home(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
-        Ok(path
-            .as_ref()
-            .strip_prefix(self.dotfiles_home_dir())?
-            .to_path_buf())
+        self.config().repo_rel_from_dotfiles_home(path)
     }
 
     fn repo_rel_from_home(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
-        Ok(path.as_ref().strip_prefix(self.home_dir())?.to_path_buf())
+        self.config().repo_rel_from_home(path)
     }
 
-    /// レポジトリ内の`path`を$HOME に"インストール"した場合の絶対パス
+    /// レポジトリ内の`path`を対応する`dest`に"インストール"した場合の絶対パス
     fn install_path(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
-        Ok(self
-            .home_dir()
-            .join(self.repo_rel_from_dotfiles_home(path)?))
+        self.config().install_path(path)
     }
 
-    /// $HOME以下のpathに対応するdotfiles/home以下の絶対パス
+    /// インストール先の`path`に対応するdotfilesレポジトリ内の絶対パス
     fn entity_path(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
-        Ok(self
-            .dotfiles_home_dir()
-            .join(self.repo_rel_from_home(path)?))
+        self.config().entity_path(path)
     }
 
-    /// `from`のリンクを`to`につくる．
-    fn create_symlink(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()>;
+    /// インストール先の`path`を退避する先のパス
+    /// $HOMEの外のパスは，ルートからの相対パスで退避する．
+    fn backup_path(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
+        let path = path.as_ref();
+        let rel = match self.repo_rel_from_home(path) {
+            Ok(rel) => rel,
+            Err(_)
+                if self.config().target_of_dest(path).is_some()
+                    || self.config().links.iter().any(|link| link.dst == path) =>
+            {
+                path.strip_prefix("/")
+                    .map(Path::to_path_buf)
+                    .unwrap_or_else(|_| path.to_path_buf())
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(self.backup_dir().join(rel))
+    }
 
-    /// `from`のリンクを`to`につくる処理をスキップする．
-    fn skip_link_creating(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
-        println!(
-            "skipped (already linked): {} -> {}",
-            from.as_ref().display(),
-            to.as_ref().display()
-        );
-        Ok(())
+    /// $HOME側のファイル`path`を退避する．
+    /// 退避先のディレクトリ(`backup_dir`)が無ければ作成されるので，それも返す．
+    fn back_up_file_from_home(&self, path: impl AsRef<Path>) -> Result<Backup> {
+        // 既定のbackup_dirは初めて退避するときに作成される．
+        let backup_root = self
+            .backup_dir()
+            .parent()
+            .unwrap_or(self.backup_dir())
+            .to_path_buf();
+        let created_dir = (!backup_root.exists()).then_some(backup_root);
+
+        Ok(Backup {
+            path: self.remove_file_from_home(path)?,
+            created_dir,
+        })
     }
 
+    /// `from`のリンクを`to`につくる．
+    fn create_symlink(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()>;
+
     fn remove_symlink_from_home(&self, path: impl AsRef<Path>) -> Result<()>;
 
     // renameを含む．
-    fn remove_file_from_home(&self, path: impl AsRef<Path>) -> Result<()>;
+    // 退避先のパスを返す．
+    fn remove_file_from_home(&self, path: impl AsRef<Path>) -> Result<PathBuf>;
 
     fn remove_dir_all_from_home(&self, path: impl AsRef<Path>) -> Result<()>;
 
@@ -70,19 +104,38 @@ pub trait Executor: HasConfig {
 
     fn remove_file_from_dotfiles_home(&self, path: impl AsRef<Path>) -> Result<()>;
 
-    fn warn_cannot_determine(&self, path: impl AsRef<Path>) -> Result<()> {
-        eprintln!(
-            "[warning] cannot determine file kind of {} (skipped)",
-            path.as_ref().display()
-        );
-        Ok(())
-    }
-
     fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()>;
+
+    /// 暗号化された`from`を復号し，mode 0600の通常ファイルとして`to`に書き込む．
+    fn decrypt_to_home(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()>;
+
+    /// `from`を暗号化して`to`に書き込む．
+    fn encrypt_to_dotfiles_home(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()>;
+
+    /// `path`のmodeを`mode`にする．シンボリックリンクの場合は参照先を変更する．
+    fn set_mode(&self, path: impl AsRef<Path>, mode: u32) -> Result<()>;
+
+    /// `dotfiles/scripts/`以下の`script`を実行し，実行状態を保存する．
+    fn run_script(&self, script: impl AsRef<Path>) -> Result<()>;
+
+    /// `paths`の変更に対するtriggerの`command`を実行する．
+    fn run_trigger(&self, command: &str, paths: &[PathBuf]) -> Result<()>;
+
+    /// dotfilesレポジトリで`paths`の変更のみを`message`でコミットする．
+    fn git_commit(&self, paths: &[PathBuf], message: &str) -> Result<()>;
+
+    /// dotfilesレポジトリをupstreamへfast-forwardする．
+    fn git_fast_forward(&self) -> Result<()>;
 }
 
 pub mod dry_executor;
+pub mod operation;
+pub mod plan_executor;
 pub mod real_executor;
+pub mod recording_executor;
 
 pub use dry_executor::*;
+pub use operation::*;
+pub use plan_executor::*;
 pub use real_executor::*;
+pub use recording_executor::*;
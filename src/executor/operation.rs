@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Result,
+    executor::{Backup, Executor},
+};
+
+/// Executorに要求されるファイル操作．
+/// 各variantはExecutorの同名メソッドに対応する．
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    CreateSymlink {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    RemoveSymlinkFromHome {
+        path: PathBuf,
+    },
+    RemoveFileFromHome {
+        path: PathBuf,
+    },
+    RemoveDirAllFromHome {
+        path: PathBuf,
+    },
+    RemoveUnknownPathFromHome {
+        path: PathBuf,
+    },
+    RemoveFileFromDotfilesHome {
+        path: PathBuf,
+    },
+    Copy {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    DecryptToHome {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    EncryptToDotfilesHome {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    SetMode {
+        path: PathBuf,
+        mode: u32,
+    },
+    RunScript {
+        path: PathBuf,
+    },
+    RunTrigger {
+        command: String,
+        paths: Vec<PathBuf>,
+    },
+    GitCommit {
+        paths: Vec<PathBuf>,
+        message: String,
+    },
+    GitFastForward,
+}
+
+impl Operation {
+    /// 操作が読み書きするパス一覧．
+    pub fn paths(&self) -> Vec<&Path> {
+        match self {
+            Operation::CreateSymlink { from, to }
+            | Operation::Copy { from, to }
+            | Operation::DecryptToHome { from, to }
+            | Operation::EncryptToDotfilesHome { from, to } => vec![from, to],
+            Operation::RemoveSymlinkFromHome { path }
+            | Operation::RemoveFileFromHome { path }
+            | Operation::RemoveDirAllFromHome { path }
+            | Operation::RemoveUnknownPathFromHome { path }
+            | Operation::RemoveFileFromDotfilesHome { path }
+            | Operation::SetMode { path, .. }
+            | Operation::RunScript { path } => vec![path],
+            Operation::RunTrigger { paths, .. } | Operation::GitCommit { paths, .. } => {
+                paths.iter().map(PathBuf::as_path).collect()
+            }
+            Operation::GitFastForward => vec![],
+        }
+    }
+
+    /// `executor`で操作を実行する．
+    /// ファイルを退避した場合は退避先のパスなどを返す．
+    pub fn perform(&self, executor: &impl Executor) -> Result<Option<Backup>> {
+        match self {
+            Operation::CreateSymlink { from, to } => executor.create_symlink(from, to)?,
+            Operation::RemoveSymlinkFromHome { path } => executor.remove_symlink_from_home(path)?,
+            Operation::RemoveFileFromHome { path } => {
+                return Ok(Some(executor.back_up_file_from_home(path)?));
+            }
+            Operation::RemoveDirAllFromHome { path } => executor.remove_dir_all_from_home(path)?,
+            Operation::RemoveUnknownPathFromHome { path } => {
+                executor.remove_unknown_path_from_home(path)?
+            }
+            Operation::RemoveFileFromDotfilesHome { path } => {
+                executor.remove_file_from_dotfiles_home(path)?
+            }
+            Operation::Copy { from, to } => executor.copy(from, to)?,
+            Operation::DecryptToHome { from, to } => executor.decrypt_to_home(from, to)?,
+            Operation::EncryptToDotfilesHome { from, to } => {
+                executor.encrypt_to_dotfiles_home(from, to)?
+            }
+            Operation::SetMode { path, mode } => executor.set_mode(path, *mode)?,
+            Operation::RunScript { path } => executor.run_script(path)?,
+            Operation::RunTrigger { command, paths } => executor.run_trigger(command, paths)?,
+            Operation::GitCommit { paths, message } => executor.git_commit(paths, message)?,
+            Operation::GitFastForward => executor.git_fast_forward()?,
+        }
+
+        Ok(None)
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{
+    executor::{HasConfig, RecordingExecutor},
+    plan::{PathState, Plan, Step},
+};
+
+/// 実際の操作は行わず，要求された操作を計画(`Plan`)として記録する．
+/// 操作の記録は`RecordingExecutor`と共通で，`into_plan`で各操作に現在のファイルの状態を添える．
+pub type PlanExecutor = RecordingExecutor;
+
+impl RecordingExecutor {
+    /// 記録した操作を計画にする．
+    ///
+    /// # NOTE
+    /// 記録中は実際の操作を行わないため，ファイルの状態は記録時点と変わらない．
+    /// したがって，各操作の前提とする状態はここでまとめて取得してよい．
+    pub fn into_plan(self) -> Plan {
+        let home_dir = self.config().home_dir.clone();
+        let dotfiles_home_dir = self.config().dotfiles_home_dir.clone();
+
+        let steps = self
+            .into_operations()
+            .into_iter()
+            .map(|operation| Step {
+                assumes: operation
+                    .paths()
+                    .into_iter()
+                    .map(PathState::capture)
+                    .collect(),
+                operation,
+            })
+            .collect();
+
+        Plan {
+            home_dir,
+            dotfiles_home_dir,
+            rev: None,
+            steps,
+        }
+    }
+}
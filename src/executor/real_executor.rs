@@ -1,11 +1,14 @@
-use std::path::Path;
-
-use anyhow::Result;
+use std::path::{Path, PathBuf};
 
 use crate::{
     config::Config,
+    error::Result,
     executor::{Executor, HasConfig},
     file_operations::*,
+    git::{commit_paths, git},
+    hooks::run_trigger,
+    scripts::run_script,
+    secret::{decrypt_to, encrypt},
 };
 
 pub struct RealExecutor {
@@ -27,11 +30,6 @@ impl HasConfig for RealExecutor {
 impl Executor for RealExecutor {
     /// `from`のリンクを`to`につくる．
     fn create_symlink(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
-        println!(
-            "created link: {} -> {}",
-            from.as_ref().display(),
-            to.as_ref().display()
-        );
         create_symlink(from, to)
     }
 
@@ -40,12 +38,11 @@ impl Executor for RealExecutor {
     }
 
     // remove(rename)
-    fn remove_file_from_home(&self, path: impl AsRef<Path>) -> Result<()> {
+    fn remove_file_from_home(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
         let path = path.as_ref();
-        let suffix = path.strip_prefix(self.home_dir())?;
-        let backup = self.backup_dir().join(suffix);
+        let backup = self.backup_path(path)?;
         rename(path, &backup)?;
-        Ok(())
+        Ok(backup)
     }
 
     fn remove_dir_all_from_home(&self, path: impl AsRef<Path>) -> Result<()> {
@@ -63,4 +60,36 @@ impl Executor for RealExecutor {
     fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
         copy(from, to)
     }
+
+    fn decrypt_to_home(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+        decrypt_to(self.config(), from, to)
+    }
+
+    fn encrypt_to_dotfiles_home(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+        write_file(to, &encrypt(self.config(), from)?)
+    }
+
+    fn set_mode(&self, path: impl AsRef<Path>, mode: u32) -> Result<()> {
+        set_mode(path, mode)
+    }
+
+    fn run_script(&self, script: impl AsRef<Path>) -> Result<()> {
+        run_script(self.config(), script.as_ref())
+    }
+
+    fn run_trigger(&self, command: &str, paths: &[PathBuf]) -> Result<()> {
+        run_trigger(self.config(), command, paths)
+    }
+
+    fn git_commit(&self, paths: &[PathBuf], message: &str) -> Result<()> {
+        commit_paths(self.dotfiles_dir(), paths, message)
+    }
+
+    fn git_fast_forward(&self) -> Result<()> {
+        git(
+            self.dotfiles_dir(),
+            ["merge", "--ff-only", "--quiet", "@{upstream}"],
+        )?;
+        Ok(())
+    }
 }
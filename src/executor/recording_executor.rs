@@ -0,0 +1,138 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::Config,
+    error::Result,
+    executor::{Executor, HasConfig, Operation},
+};
+
+/// 実際の操作は行わず，要求された操作を順に記録する．
+/// 記録した操作は`operations`で取り出すか，`into_plan`で計画にする(`PlanExecutor`)．
+pub struct RecordingExecutor {
+    config: Config,
+    operations: RefCell<Vec<Operation>>,
+}
+
+impl RecordingExecutor {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            operations: RefCell::new(vec![]),
+        }
+    }
+
+    /// これまでに記録した操作一覧．
+    pub fn operations(&self) -> Vec<Operation> {
+        self.operations.borrow().clone()
+    }
+
+    pub fn into_operations(self) -> Vec<Operation> {
+        self.operations.into_inner()
+    }
+
+    fn record(&self, operation: Operation) -> Result<()> {
+        self.operations.borrow_mut().push(operation);
+        Ok(())
+    }
+}
+
+impl HasConfig for RecordingExecutor {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+}
+
+impl Executor for RecordingExecutor {
+    fn create_symlink(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+        self.record(Operation::CreateSymlink {
+            from: from.as_ref().to_path_buf(),
+            to: to.as_ref().to_path_buf(),
+        })
+    }
+
+    fn remove_symlink_from_home(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.record(Operation::RemoveSymlinkFromHome {
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    fn remove_file_from_home(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
+        self.record(Operation::RemoveFileFromHome {
+            path: path.as_ref().to_path_buf(),
+        })?;
+        self.backup_path(path)
+    }
+
+    fn remove_dir_all_from_home(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.record(Operation::RemoveDirAllFromHome {
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    fn remove_unknown_path_from_home(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.record(Operation::RemoveUnknownPathFromHome {
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    fn remove_file_from_dotfiles_home(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.record(Operation::RemoveFileFromDotfilesHome {
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+        self.record(Operation::Copy {
+            from: from.as_ref().to_path_buf(),
+            to: to.as_ref().to_path_buf(),
+        })
+    }
+
+    fn decrypt_to_home(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+        self.record(Operation::DecryptToHome {
+            from: from.as_ref().to_path_buf(),
+            to: to.as_ref().to_path_buf(),
+        })
+    }
+
+    fn encrypt_to_dotfiles_home(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+        self.record(Operation::EncryptToDotfilesHome {
+            from: from.as_ref().to_path_buf(),
+            to: to.as_ref().to_path_buf(),
+        })
+    }
+
+    fn set_mode(&self, path: impl AsRef<Path>, mode: u32) -> Result<()> {
+        self.record(Operation::SetMode {
+            path: path.as_ref().to_path_buf(),
+            mode,
+        })
+    }
+
+    fn run_script(&self, script: impl AsRef<Path>) -> Result<()> {
+        self.record(Operation::RunScript {
+            path: script.as_ref().to_path_buf(),
+        })
+    }
+
+    fn run_trigger(&self, command: &str, paths: &[PathBuf]) -> Result<()> {
+        self.record(Operation::RunTrigger {
+            command: command.to_string(),
+            paths: paths.to_vec(),
+        })
+    }
+
+    fn git_commit(&self, paths: &[PathBuf], message: &str) -> Result<()> {
+        self.record(Operation::GitCommit {
+            paths: paths.to_vec(),
+            message: message.to_string(),
+        })
+    }
+
+    fn git_fast_forward(&self) -> Result<()> {
+        self.record(Operation::GitFastForward)
+    }
+}
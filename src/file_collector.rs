@@ -3,9 +3,23 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use anyhow::Result;
+use crate::{
+    config::{Config, Link},
+    error::Result,
+    file_kind::*,
+    report::PathPair,
+};
+
+pub struct CollectedFiles {
+    // 通常ファイルのパス一覧
+    pub files: Vec<PathBuf>,
 
-use crate::file_kind::*;
+    // シンボリックリンクのパス一覧
+    pub links: Vec<PathBuf>,
+
+    // 探索中に発生した警告
+    pub warnings: Vec<String>,
+}
 
 /// 指定したパス以下を再帰的に探索し，通常ファイルとシンボリックリンク(壊れたリンクを含む)を収集する．
 ///
@@ -13,20 +27,15 @@ use crate::file_kind::*;
 ///
 /// - `path`: 探索を開始するディレクトリまたはファイルのパス
 ///
-/// # 返り値
-///
-/// `(files, links)`のタプル:
-/// - `files`: 通常ファイルのパス一覧
-/// - `links`: シンボリックリンクのパス一覧
-///
 /// # NOTE
 /// - 引数で指定したパスが `files` または `links` に入る可能性がある．
 /// - シンボリックリンク，通常ファイル，ディレクトリのどれでもないパスは
-///   `Unknown file` として警告を出力して無視する．
+///   `Unknown file` として警告に記録して無視する．
 /// - ディレクトリへのシンボリックリンクは辿らない．
-pub fn collect_files_and_links(path: impl AsRef<Path>) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+pub fn collect_files_and_links(path: impl AsRef<Path>) -> Result<CollectedFiles> {
     let mut files = vec![];
     let mut links = vec![];
+    let mut warnings = vec![];
 
     let mut stack = vec![path.as_ref().to_path_buf()];
 
@@ -44,26 +53,26 @@ pub fn collect_files_and_links(path: impl AsRef<Path>) -> Result<(Vec<PathBuf>,
                     for entry in entries {
                         match entry {
                             Ok(e) => stack.push(e.path()),
-                            Err(e) => eprintln!(
-                                "[warning] failed to read entry in {}: {}",
+                            Err(e) => warnings.push(format!(
+                                "failed to read entry in {}: {}",
                                 path.display(),
                                 e
-                            ),
+                            )),
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("[warning] failed to read_dir {}: {}", path.display(), e);
+                    warnings.push(format!("failed to read_dir {}: {}", path.display(), e));
                 }
             },
             FileKind::Unknown => {
-                eprintln!("[warning] unknown file type: {}", path.display());
+                warnings.push(format!("unknown file type: {}", path.display()));
             }
             FileKind::Error => {
-                eprintln!("[warning] error path: {}", path.display());
+                warnings.push(format!("error path: {}", path.display()));
             }
             FileKind::NotFound => {
-                eprintln!("[warning] not found: {}", path.display());
+                warnings.push(format!("not found: {}", path.display()));
             }
         }
     }
@@ -73,5 +82,98 @@ pub fn collect_files_and_links(path: impl AsRef<Path>) -> Result<(Vec<PathBuf>,
     links.sort_unstable();
     links.dedup();
 
-    Ok((files, links))
+    Ok(CollectedFiles {
+        files,
+        links,
+        warnings,
+    })
+}
+
+/// `config.targets`の各`source`以下を探索し，結果をまとめる．
+pub fn collect_managed_files(config: &Config) -> Result<CollectedFiles> {
+    let mut collected = CollectedFiles {
+        files: vec![],
+        links: vec![],
+        warnings: vec![],
+    };
+
+    for target in &config.targets {
+        let CollectedFiles {
+            files,
+            links,
+            warnings,
+        } = collect_files_and_links(&target.source)?;
+        collected.files.extend(files);
+        collected.links.extend(links);
+        collected.warnings.extend(warnings);
+    }
+
+    // `source`が入れ子になっている場合の重複を除く．
+    collected.files.sort_unstable();
+    collected.files.dedup();
+    collected.links.sort_unstable();
+    collected.links.dedup();
+
+    Ok(collected)
+}
+
+/// 管理対象ファイル(`from`)とインストール先(`to`)の組．
+pub struct InstallPairs {
+    // ミラーされたファイルと，`[[link]]`で宣言したもの．
+    pub pairs: Vec<PathPair>,
+
+    // dotfiles内のシンボリックリンクのパス一覧
+    pub links: Vec<PathBuf>,
+
+    pub warnings: Vec<String>,
+}
+
+/// ミラーされたファイルと`[[link]]`の宣言から，インストールする組を集める．
+///
+/// # NOTE
+/// - `src`が通常ファイルでない宣言は警告して無視する．
+/// - インストール先が他の組と重複する宣言は警告して無視する．
+pub fn collect_install_pairs(config: &Config) -> Result<InstallPairs> {
+    let CollectedFiles {
+        files,
+        links,
+        mut warnings,
+    } = collect_managed_files(config)?;
+
+    let mut pairs = files
+        .into_iter()
+        .map(|from| {
+            Ok(PathPair {
+                to: config.install_path(&from)?,
+                from,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    for Link { src, dst } in &config.links {
+        if !is_file(src) {
+            warnings.push(format!("link source {} is not a file.", src.display()));
+            continue;
+        }
+
+        if pairs.iter().any(|pair| pair.to == *dst) {
+            warnings.push(format!(
+                "{} is already an install path. link from {} is ignored.",
+                dst.display(),
+                src.display()
+            ));
+            continue;
+        }
+
+        pairs.push(PathPair {
+            from: src.clone(),
+            to: dst.clone(),
+        });
+    }
+
+    Ok(InstallPairs {
+        pairs,
+        links,
+        warnings,
+    })
 }
@@ -1,7 +1,11 @@
 #![allow(dead_code)]
 
-use std::{fs, os::unix::fs::MetadataExt, path::Path};
+use std::{fmt, fs, os::unix::fs::MetadataExt, path::Path};
 
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FileKind {
     Symlink,
     File,
@@ -18,6 +22,34 @@ pub enum FileKind {
     Error,
 }
 
+impl FileKind {
+    /// 機械可読な出力で使う名前．serdeでの名前と同じ．
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileKind::Symlink => "symlink",
+            FileKind::File => "file",
+            FileKind::Dir => "dir",
+            FileKind::Unknown => "unknown",
+            FileKind::NotFound => "not_found",
+            FileKind::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            FileKind::Symlink => "a symlink",
+            FileKind::File => "a file",
+            FileKind::Dir => "a directory",
+            FileKind::Unknown => "an unknown path",
+            FileKind::NotFound => "not found",
+            FileKind::Error => "an error path",
+        };
+        f.write_str(s)
+    }
+}
+
 pub fn file_kind(path: impl AsRef<Path>) -> FileKind {
     match fs::symlink_metadata(path) {
         Ok(meta) => {
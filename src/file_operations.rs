@@ -1,14 +1,19 @@
-use std::{fs, os::unix, path::Path};
-
-use anyhow::{Context, Result, anyhow};
-
-use crate::file_kind::*;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::{self, fs::OpenOptionsExt, fs::PermissionsExt},
+    path::Path,
+};
+
+use crate::{
+    error::{Error, Result},
+    file_kind::*,
+};
 
 /// `path`の親ディレクトリを作成する．
 pub fn create_parent_dir(path: impl AsRef<Path>) -> Result<()> {
     if let Some(parent) = path.as_ref().parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("failed to create parent directory: {}", parent.display()))?;
+        fs::create_dir_all(parent).map_err(|e| Error::io("create parent directory", parent, e))?;
     }
     Ok(())
 }
@@ -19,10 +24,13 @@ pub fn remove_file(path: impl AsRef<Path>) -> Result<()> {
     let path = path.as_ref();
 
     if !is_file(path) {
-        return Err(anyhow!("{} is not a file.", path.display()));
+        return Err(Error::UnexpectedFileKind {
+            path: path.to_path_buf(),
+            expected: FileKind::File,
+        });
     }
 
-    fs::remove_file(path).with_context(|| format!("failed to remove file: {}", path.display()))?;
+    fs::remove_file(path).map_err(|e| Error::io("remove file", path, e))?;
 
     Ok(())
 }
@@ -33,11 +41,13 @@ pub fn remove_symlink(path: impl AsRef<Path>) -> Result<()> {
     let path = path.as_ref();
 
     if !is_symlink(path) {
-        return Err(anyhow!("{} is not a symlink.", path.display()));
+        return Err(Error::UnexpectedFileKind {
+            path: path.to_path_buf(),
+            expected: FileKind::Symlink,
+        });
     }
 
-    fs::remove_file(path)
-        .with_context(|| format!("failed to remove symlink: {}", path.display()))?;
+    fs::remove_file(path).map_err(|e| Error::io("remove symlink", path, e))?;
 
     Ok(())
 }
@@ -48,11 +58,13 @@ pub fn remove_unknown_path(path: impl AsRef<Path>) -> Result<()> {
     let path = path.as_ref();
 
     if !is_unknown(path) {
-        return Err(anyhow!("{} is not an unknown path.", path.display()));
+        return Err(Error::UnexpectedFileKind {
+            path: path.to_path_buf(),
+            expected: FileKind::Unknown,
+        });
     }
 
-    fs::remove_file(path)
-        .with_context(|| format!("failed to remove unknown path: {}", path.display()))?;
+    fs::remove_file(path).map_err(|e| Error::io("remove unknown path", path, e))?;
 
     Ok(())
 }
@@ -63,11 +75,13 @@ pub fn remove_dir_all(path: impl AsRef<Path>) -> Result<()> {
     let path = path.as_ref();
 
     if !is_dir(path) {
-        return Err(anyhow!("{} is not a directory.", path.display()));
+        return Err(Error::UnexpectedFileKind {
+            path: path.to_path_buf(),
+            expected: FileKind::Dir,
+        });
     }
 
-    fs::remove_dir_all(path)
-        .with_context(|| format!("failed to remove dir: {}", path.display()))?;
+    fs::remove_dir_all(path).map_err(|e| Error::io("remove dir", path, e))?;
 
     Ok(())
 }
@@ -80,8 +94,7 @@ pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
 
     create_parent_dir(to)?;
 
-    fs::rename(from, to)
-        .with_context(|| format!("failed to rename: {} -> {}", from.display(), to.display()))?;
+    fs::rename(from, to).map_err(|e| Error::io_pair("rename", from, to, e))?;
 
     Ok(())
 }
@@ -94,8 +107,7 @@ pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
 
     create_parent_dir(to)?;
 
-    fs::copy(from, to)
-        .with_context(|| format!("failed to copy: {} -> {}", from.display(), to.display()))?;
+    fs::copy(from, to).map_err(|e| Error::io_pair("copy", from, to, e))?;
 
     Ok(())
 }
@@ -106,13 +118,54 @@ pub fn create_symlink(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()
     let from = from.as_ref();
     let to = to.as_ref();
     create_parent_dir(to)?;
-    unix::fs::symlink(from, to).with_context(|| {
-        format!(
-            "failed to create link: {} -> {}",
-            from.display(),
-            to.display()
-        )
-    })?;
+    unix::fs::symlink(from, to).map_err(|e| Error::io_pair("create link", from, to, e))?;
+
+    Ok(())
+}
+
+/// `path`のmodeを`mode`にする．シンボリックリンクの場合は参照先を変更する．
+pub fn set_mode(path: impl AsRef<Path>, mode: u32) -> Result<()> {
+    let path = path.as_ref();
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|e| Error::io("set permissions of", path, e))?;
+
+    Ok(())
+}
+
+/// `content`を`path`に書き込む．
+/// `to`に既存ファイルがあれば上書きする．
+pub fn write_file(path: impl AsRef<Path>, content: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+
+    create_parent_dir(path)?;
+
+    fs::write(path, content).map_err(|e| Error::io("write", path, e))?;
+
+    Ok(())
+}
+
+/// `content`を所有者のみ読み書きできる(0600)通常ファイルとして`path`に書き込む．
+/// `path`に既存ファイルがあれば上書きする．
+pub fn write_private_file(path: impl AsRef<Path>, content: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+
+    create_parent_dir(path)?;
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| Error::io("open", path, e))?;
+
+    // 既存ファイルの場合modeは変わらないので明示的に設定する．
+    file.set_permissions(fs::Permissions::from_mode(0o600))
+        .map_err(|e| Error::io("set permissions of", path, e))?;
+
+    file.write_all(content)
+        .map_err(|e| Error::io("write", path, e))?;
 
     Ok(())
 }
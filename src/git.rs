@@ -0,0 +1,98 @@
+//! ローカルの`git`コマンドでdotfilesレポジトリを操作する．
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::error::{Error, Result};
+
+/// `repo`で`git args...`を実行し，標準出力を返す．
+/// 終了ステータスが0以外ならエラー．
+pub(crate) fn git<I, S>(repo: &Path, args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let args = args
+        .into_iter()
+        .map(|arg| arg.as_ref().to_os_string())
+        .collect::<Vec<_>>();
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(&args)
+        .output()
+        .map_err(|e| Error::io("run git in", repo, e))?;
+
+    if !output.status.success() {
+        return Err(Error::Git {
+            repo: repo.to_path_buf(),
+            args: args
+                .iter()
+                .map(|arg| arg.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" "),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `repo`がgitの作業ツリー内ならtrue．
+pub(crate) fn is_work_tree(repo: &Path) -> bool {
+    git(repo, ["rev-parse", "--is-inside-work-tree"]).is_ok_and(|out| out.trim() == "true")
+}
+
+/// `repo`がgitの作業ツリー内でなければエラー．
+pub(crate) fn ensure_work_tree(repo: &Path) -> Result<()> {
+    if is_work_tree(repo) {
+        Ok(())
+    } else {
+        Err(Error::NotAGitRepo {
+            repo: repo.to_path_buf(),
+        })
+    }
+}
+
+/// `path`がgitで追跡されていればtrue．
+pub(crate) fn is_tracked(repo: &Path, path: &Path) -> bool {
+    git(
+        repo,
+        [
+            OsStr::new("ls-files"),
+            OsStr::new("--error-unmatch"),
+            OsStr::new("--"),
+            path.as_os_str(),
+        ],
+    )
+    .is_ok()
+}
+
+/// `paths`の変更(追加・削除)をステージし，それらのみを`message`でコミットする．
+pub(crate) fn commit_paths(repo: &Path, paths: &[PathBuf], message: &str) -> Result<()> {
+    git(
+        repo,
+        [OsStr::new("add"), OsStr::new("-A"), OsStr::new("--")]
+            .into_iter()
+            .chain(paths.iter().map(|path| path.as_os_str())),
+    )?;
+
+    git(
+        repo,
+        [
+            OsStr::new("commit"),
+            OsStr::new("--quiet"),
+            OsStr::new("-m"),
+            OsStr::new(message),
+            OsStr::new("--"),
+        ]
+        .into_iter()
+        .chain(paths.iter().map(|path| path.as_os_str())),
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! 設定ファイルで$HOMEからの相対パスを指定するためのglob．
+
+use std::path::{Component, Path};
+
+pub(crate) fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// `pattern`が相対パス`path`に一致すればtrue．
+///
+/// - `*`はパス区切りを除く任意の文字列，`?`はパス区切りを除く任意の1文字に一致する．
+/// - `**`だけの要素は0個以上の任意の要素に一致する．
+pub(crate) fn glob_match(pattern: &str, path: &Path) -> bool {
+    let pattern = pattern
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>();
+    let path = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    match_parts(&pattern, &path)
+}
+
+fn match_parts(pattern: &[&str], path: &[&str]) -> bool {
+    wildcard_match(
+        pattern,
+        path,
+        |part| *part == "**",
+        |pattern, part| match_part(pattern, part),
+    )
+}
+
+fn match_part(pattern: &str, part: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let part = part.chars().collect::<Vec<_>>();
+
+    wildcard_match(&pattern, &part, |c| *c == '*', |c, d| *c == '?' || c == d)
+}
+
+/// `pattern`が`text`に一致すればtrue．
+/// `is_star`を満たす要素は0個以上の任意の要素に，それ以外は`matches_one`を満たす1要素に一致する．
+///
+/// # NOTE
+/// 直前の`is_star`の要素に一致させる長さだけを1つずつ伸ばして再試行する．
+/// それより前の`is_star`の要素を伸ばしても一致するものは増えないので，
+/// `**/**/**/x`のようなパターンでも時間は`pattern.len() * text.len()`に比例する．
+fn wildcard_match<P, T>(
+    pattern: &[P],
+    text: &[T],
+    is_star: impl Fn(&P) -> bool,
+    matches_one: impl Fn(&P, &T) -> bool,
+) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // (直前の`is_star`の要素の位置, それに一致させた部分の終わり)
+    let mut star = None;
+
+    while t < text.len() {
+        if p < pattern.len() && is_star(&pattern[p]) {
+            star = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && matches_one(&pattern[p], &text[t]) {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = star {
+            star = Some((star_p, star_t + 1));
+            p = star_p + 1;
+            t = star_t + 1;
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(is_star)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_glob() {
+        assert!(is_glob(".ssh/*"));
+        assert!(is_glob(".config/?"));
+        assert!(!is_glob(".ssh/config"));
+    }
+
+    #[test]
+    fn matches_literal_path() {
+        assert!(glob_match(".ssh/config", Path::new(".ssh/config")));
+        assert!(glob_match("./.ssh//config", Path::new(".ssh/config")));
+        assert!(!glob_match(".ssh/config", Path::new(".ssh/config.d")));
+        assert!(!glob_match(".ssh", Path::new(".ssh/config")));
+    }
+
+    #[test]
+    fn star_and_question_do_not_cross_separator() {
+        assert!(glob_match(".ssh/*", Path::new(".ssh/id_ed25519")));
+        assert!(glob_match(".ssh/id_*.pub", Path::new(".ssh/id_rsa.pub")));
+        assert!(glob_match(".ssh/id_*", Path::new(".ssh/id_")));
+        assert!(!glob_match(".ssh/*", Path::new(".ssh/keys/id_rsa")));
+        assert!(glob_match("?.txt", Path::new("a.txt")));
+        assert!(!glob_match("?.txt", Path::new("ab.txt")));
+        assert!(!glob_match("a?b", Path::new("a/b")));
+    }
+
+    #[test]
+    fn double_star_matches_any_number_of_components() {
+        assert!(glob_match(".config/**", Path::new(".config")));
+        assert!(glob_match(".config/**", Path::new(".config/nvim/init.lua")));
+        assert!(glob_match("**/*.sh", Path::new("run.sh")));
+        assert!(glob_match("**/*.sh", Path::new(".local/bin/run.sh")));
+        assert!(glob_match("a/**/b/**/c", Path::new("a/b/x/b/y/c")));
+        assert!(!glob_match("a/**/b/**/c", Path::new("a/c/b")));
+        assert!(!glob_match("**/*.sh", Path::new(".local/bin/run.py")));
+    }
+
+    #[test]
+    fn backtracking_is_bounded() {
+        let pattern = format!("{}x", "**/".repeat(32));
+        let path = "a/".repeat(64);
+        assert!(!glob_match(&pattern, Path::new(&path)));
+        assert!(glob_match(&pattern, Path::new(&format!("{}x", path))));
+
+        let pattern = format!("{}b", "*a".repeat(32));
+        assert!(!glob_match(&pattern, Path::new(&"a".repeat(128))));
+    }
+}
@@ -0,0 +1,292 @@
+//! コマンドの前後に実行するフックと，特定のファイルの変更時に実行するtrigger．
+//!
+//! フックは`dotfiles/hooks/<名前>`のファイルと，設定ファイルの`[hooks]`のコマンドを実行する．
+//! triggerは設定ファイルの`[triggers]`のコマンドを実行する．
+
+use std::{
+    path::PathBuf,
+    process::{Command, ExitStatus},
+};
+
+use crate::{
+    config::{Config, HooksConfig},
+    error::{Error, Result},
+    glob::glob_match,
+    permissions::is_executable,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookStage {
+    Pre,
+    Post,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    Install,
+    Add,
+    Remove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hook {
+    pub stage: HookStage,
+    pub event: HookEvent,
+}
+
+impl Hook {
+    pub fn pre(event: HookEvent) -> Self {
+        Hook {
+            stage: HookStage::Pre,
+            event,
+        }
+    }
+
+    pub fn post(event: HookEvent) -> Self {
+        Hook {
+            stage: HookStage::Post,
+            event,
+        }
+    }
+
+    /// `pre-install`のような名前．`dotfiles/hooks/`以下のファイル名，`[hooks]`のキーと同じ．
+    pub fn name(&self) -> &'static str {
+        match (self.stage, self.event) {
+            (HookStage::Pre, HookEvent::Install) => "pre-install",
+            (HookStage::Pre, HookEvent::Add) => "pre-add",
+            (HookStage::Pre, HookEvent::Remove) => "pre-remove",
+            (HookStage::Post, HookEvent::Install) => "post-install",
+            (HookStage::Post, HookEvent::Add) => "post-add",
+            (HookStage::Post, HookEvent::Remove) => "post-remove",
+        }
+    }
+}
+
+impl HooksConfig {
+    /// `[hooks]`で`hook`に指定されたコマンド．
+    pub fn command(&self, hook: Hook) -> Option<&str> {
+        match (hook.stage, hook.event) {
+            (HookStage::Pre, HookEvent::Install) => self.pre_install.as_deref(),
+            (HookStage::Pre, HookEvent::Add) => self.pre_add.as_deref(),
+            (HookStage::Pre, HookEvent::Remove) => self.pre_remove.as_deref(),
+            (HookStage::Post, HookEvent::Install) => self.post_install.as_deref(),
+            (HookStage::Post, HookEvent::Add) => self.post_add.as_deref(),
+            (HookStage::Post, HookEvent::Remove) => self.post_remove.as_deref(),
+        }
+    }
+}
+
+/// `hook`として実行するコマンド．
+enum HookCommand<'a> {
+    // `dotfiles/hooks/`以下の実行可能ファイル．
+    File(PathBuf),
+
+    // `[hooks]`に書かれたコマンド．`sh -c`で実行する．
+    Shell(&'a str),
+}
+
+impl HookCommand<'_> {
+    fn display(&self) -> String {
+        match self {
+            HookCommand::File(path) => path.display().to_string(),
+            HookCommand::Shell(command) => command.to_string(),
+        }
+    }
+}
+
+fn hook_commands(config: &Config, hook: Hook) -> Vec<HookCommand<'_>> {
+    let file = config.dotfiles_dir.join("hooks").join(hook.name());
+
+    let mut commands = vec![];
+    if file.is_file() {
+        commands.push(HookCommand::File(file));
+    }
+    if let Some(command) = config.hooks.command(hook) {
+        commands.push(HookCommand::Shell(command));
+    }
+    commands
+}
+
+/// `hook`を実行する．定義されていなければ何もしない．
+///
+/// dotfilesレポジトリのルートで，以下の環境変数を設定して実行する．
+/// 標準出力・標準エラー出力はそのまま表示する．
+///
+/// - `DOTKOKE_HOOK`: フックの名前(`post-install`など)
+/// - `DOTKOKE_CHANGED_PATHS`: 変更された(pre-hookの場合は変更される予定の)パス．改行区切り
+/// - `DOTKOKE_DRY_RUN`: dry-runなら`1`，そうでなければ`0`
+/// - `DOTKOKE_HOME`: $HOME
+/// - `DOTKOKE_DOTFILES`: dotfilesレポジトリのルート
+pub fn run_hook(
+    config: &Config,
+    hook: Hook,
+    changed_paths: &[PathBuf],
+    dry_run: bool,
+) -> Result<()> {
+    for command in hook_commands(config, hook) {
+        let mut process = match &command {
+            // 実行できないファイルを黙って飛ばすと，pre-hookでの確認などが行われないまま進んでしまう．
+            HookCommand::File(path) if !is_executable(path) => {
+                return Err(Error::HookNotExecutable { path: path.clone() });
+            }
+            HookCommand::File(path) => Command::new(path),
+            HookCommand::Shell(command) => shell(command),
+        };
+
+        process
+            .env("DOTKOKE_HOOK", hook.name())
+            .env("DOTKOKE_DRY_RUN", if dry_run { "1" } else { "0" });
+
+        let status = run(config, process, changed_paths).map_err(|e| match &command {
+            HookCommand::File(path) => Error::io("run hook", path, e),
+            HookCommand::Shell(_) => Error::io("run hook in", &config.dotfiles_dir, e),
+        })?;
+
+        if !status.success() {
+            return Err(Error::Hook {
+                hook: hook.name(),
+                command: command.display(),
+                status: status.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// `[triggers]`の1項目．
+/// `pattern`($HOMEからの相対パスまたはglob)に一致するファイルが変更されたら`command`を実行する．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trigger {
+    pub pattern: String,
+    pub command: String,
+}
+
+/// 実行する(した)trigger．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggerRun {
+    pub command: String,
+
+    // triggerに一致した，変更された$HOME側のパス．
+    pub paths: Vec<PathBuf>,
+}
+
+impl Config {
+    /// 変更された$HOME側のパス`changed`に対して実行するtrigger．
+    /// 同じコマンドは1回にまとめる．
+    pub(crate) fn triggers_for(&self, changed: &[PathBuf]) -> Vec<TriggerRun> {
+        let mut runs: Vec<TriggerRun> = vec![];
+
+        for Trigger { pattern, command } in &self.triggers {
+            let paths = changed.iter().filter(|path| {
+                self.repo_rel_from_home(path)
+                    .is_ok_and(|rel| glob_match(pattern, &rel))
+            });
+
+            let index = match runs.iter().position(|run| run.command == *command) {
+                Some(index) => index,
+                None => {
+                    runs.push(TriggerRun {
+                        command: command.clone(),
+                        paths: vec![],
+                    });
+                    runs.len() - 1
+                }
+            };
+
+            for path in paths {
+                if !runs[index].paths.contains(path) {
+                    runs[index].paths.push(path.clone());
+                }
+            }
+        }
+
+        runs.retain(|run| !run.paths.is_empty());
+        runs
+    }
+}
+
+/// triggerの`command`を実行する．環境変数はフックと同じ(`DOTKOKE_HOOK`，`DOTKOKE_DRY_RUN`を除く)．
+pub(crate) fn run_trigger(config: &Config, command: &str, paths: &[PathBuf]) -> Result<()> {
+    let status = run(config, shell(command), paths)
+        .map_err(|e| Error::io("run trigger in", &config.dotfiles_dir, e))?;
+
+    if !status.success() {
+        return Err(Error::Trigger {
+            command: command.to_string(),
+            status: status.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+fn shell(command: &str) -> Command {
+    let mut process = Command::new("sh");
+    process.arg("-c").arg(command);
+    process
+}
+
+/// dotfilesレポジトリのルートで，共通の環境変数を設定して`process`を実行する．
+fn run(
+    config: &Config,
+    mut process: Command,
+    changed_paths: &[PathBuf],
+) -> std::io::Result<ExitStatus> {
+    let changed_paths = changed_paths
+        .iter()
+        .map(|path| path.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    process
+        .current_dir(&config.dotfiles_dir)
+        .env("DOTKOKE_CHANGED_PATHS", changed_paths)
+        .env("DOTKOKE_HOME", &config.home_dir)
+        .env("DOTKOKE_DOTFILES", &config.dotfiles_dir)
+        .status()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn triggers_match_globs_and_merge_identical_commands() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        for sub in ["dotfiles/home", "home"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        fs::write(
+            root.join("dotkoke_config.toml"),
+            "version = 1\n\n[general]\ndotfiles = \"dotfiles\"\nhome = \"home\"\n\n\
+             [triggers]\n\".config/fish/*\" = \"fish-reload\"\n\".tmux.conf\" = \"tmux-reload\"\n\
+             \".zprofile\" = \"reload\"\n\".zshrc\" = \"reload\"\n",
+        )
+        .unwrap();
+        let config = Config::read(root.join("dotkoke_config.toml")).unwrap();
+
+        let home = root.join("home");
+        let changed =
+            [".zshrc", ".config/fish/config.fish", ".vimrc", ".zprofile"].map(|rel| home.join(rel));
+
+        assert_eq!(
+            config.triggers_for(&changed),
+            vec![
+                TriggerRun {
+                    command: "fish-reload".to_string(),
+                    paths: vec![home.join(".config/fish/config.fish")],
+                },
+                TriggerRun {
+                    command: "reload".to_string(),
+                    paths: vec![home.join(".zprofile"), home.join(".zshrc")],
+                },
+            ]
+        );
+    }
+}
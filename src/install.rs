@@ -1,53 +1,180 @@
-use anyhow::Result;
+use std::{collections::HashMap, path::Path};
 
-use crate::{executor::Executor, file_collector::*, file_kind::*};
+use crate::{
+    error::Result,
+    executor::{Backup, Executor},
+    file_collector::*,
+    file_kind::*,
+    permissions::mode_of,
+    report::*,
+    scripts::pending_scripts,
+    secret::is_decrypted_up_to_date,
+};
 
-pub fn install(executor: impl Executor) -> Result<()> {
-    let (files, links) = collect_files_and_links(executor.dotfiles_home_dir())?;
+pub fn install(executor: &impl Executor) -> Result<InstallReport> {
+    let mut report = InstallReport::default();
+
+    let InstallPairs {
+        pairs,
+        links,
+        warnings,
+    } = collect_install_pairs(executor.config())?;
+
+    report.warnings.extend(warnings);
 
     if !links.is_empty() {
-        eprintln!(
-            "[warning] symlink(s) exist in {} (they will be ignored).",
-            executor.dotfiles_home_dir().display()
-        );
+        report.warnings.push(format!(
+            "symlink(s) exist in {} (they will be ignored).",
+            executor.dotfiles_dir().display()
+        ));
     }
     drop(links);
 
-    for from in files {
+    let mut installed = vec![];
+
+    // リンクを作成・復号したインストール先．
+    let mut changed = vec![];
+
+    // 暗号化されたファイルのインストール先．modeの指定がなければ0600にする．
+    let mut secrets = vec![];
+
+    // このinstallの後のmode．dry-runでもリンクの作成や復号の後の状態で比較するため．
+    let mut modes_after_install = HashMap::new();
+
+    for PathPair { from, to } in pairs {
         assert!(!is_symlink(&from));
 
-        let to = executor.install_path(&from)?;
+        installed.push(to.clone());
 
-        // fromのリンクをtoにつくる．
+        if executor.config().is_secret(&from) {
+            // 暗号化されたファイルは復号してtoに書き込む．
 
-        if is_symlink_pointing_to(&to, &from) {
-            executor.skip_link_creating(&from, &to)?;
-            continue;
-        }
+            secrets.push(to.clone());
 
-        match file_kind(&to) {
-            FileKind::Symlink => {
-                executor.remove_symlink_from_home(&to)?;
-            }
-            FileKind::File => {
-                executor.remove_file_from_home(&to)?;
+            if is_file(&to) && is_decrypted_up_to_date(executor.config(), &from, &to)? {
+                report.skipped.push(Skipped {
+                    path: to,
+                    reason: SkipReason::UpToDate,
+                });
+                continue;
             }
-            FileKind::Dir => {
-                // TODO: 将来的にはバックアップをとるよう修正予定．
-                executor.remove_dir_all_from_home(&to)?;
+
+            if !clear_install_path(executor, &to, &mut report)? {
+                continue;
             }
-            FileKind::Unknown => {
-                executor.remove_unknown_path_from_home(&to)?;
+
+            executor.decrypt_to_home(&from, &to)?;
+            modes_after_install.insert(to.clone(), Some(0o600));
+            changed.push(to.clone());
+            report.decrypted.push(PathPair { from, to });
+        } else {
+            // fromのリンクをtoにつくる．
+
+            modes_after_install.insert(to.clone(), mode_of(&from));
+
+            if is_symlink_pointing_to(&to, &from) {
+                report.skipped.push(Skipped {
+                    path: to,
+                    reason: SkipReason::AlreadyLinked,
+                });
+                continue;
             }
-            FileKind::NotFound => {}
-            FileKind::Error => {
-                executor.warn_cannot_determine(&to)?;
+
+            if !clear_install_path(executor, &to, &mut report)? {
                 continue;
             }
+
+            executor.create_symlink(&from, &to)?;
+            changed.push(to.clone());
+            report.created_links.push(PathPair { from, to });
         }
+    }
+
+    // 状態が判別できずスキップしたパスには触れない．
+    installed.retain(|to| {
+        !report
+            .skipped
+            .iter()
+            .any(|skipped| skipped.reason == SkipReason::CannotDetermine && skipped.path == *to)
+    });
 
-        executor.create_symlink(&from, &to)?;
+    // 親ディレクトリを作成した後に変更するので，ディレクトリも含めここでまとめて行う．
+    let mut targets = executor.config().mode_targets(&installed);
+    for to in secrets {
+        if !targets.iter().any(|(path, _)| *path == to) {
+            targets.push((to, 0o600));
+        }
+    }
+
+    for (path, mode) in targets {
+        let current = match modes_after_install.get(&path) {
+            Some(mode) => *mode,
+            None => mode_of(&path),
+        };
+        if current != Some(mode) {
+            executor.set_mode(&path, mode)?;
+            report.mode_changes.push(ModeChange { path, mode });
+        }
+    }
+
+    // `install --rev`の場合も対象のrevisionのスクリプトを実行する．
+    // dotfiles/home -> dotfiles/scripts
+    let scripts_dir = executor.dotfiles_home_dir().with_file_name("scripts");
+    for script in pending_scripts(executor.config(), &scripts_dir, &mut report.warnings)? {
+        executor.run_script(&script)?;
+        report.scripts.push(script);
+    }
+
+    for run in executor.config().triggers_for(&changed) {
+        executor.run_trigger(&run.command, &run.paths)?;
+        report.triggers.push(run);
+    }
+
+    Ok(report)
+}
+
+/// インストール先`to`に既存のファイルなどがあれば退避・削除する．
+/// `to`の状態が判別できずスキップする場合はfalseを返す．
+fn clear_install_path(
+    executor: &impl Executor,
+    to: &Path,
+    report: &mut InstallReport,
+) -> Result<bool> {
+    match file_kind(to) {
+        FileKind::Symlink => {
+            executor.remove_symlink_from_home(to)?;
+            report.removed.push(to.to_path_buf());
+        }
+        FileKind::File => {
+            let Backup { path, created_dir } = executor.back_up_file_from_home(to)?;
+            if let Some(dir) = created_dir
+                && !report.created_dirs.contains(&dir)
+            {
+                report.created_dirs.push(dir);
+            }
+            report.backups.push(PathPair {
+                from: to.to_path_buf(),
+                to: path,
+            });
+        }
+        FileKind::Dir => {
+            // TODO: 将来的にはバックアップをとるよう修正予定．
+            executor.remove_dir_all_from_home(to)?;
+            report.removed.push(to.to_path_buf());
+        }
+        FileKind::Unknown => {
+            executor.remove_unknown_path_from_home(to)?;
+            report.removed.push(to.to_path_buf());
+        }
+        FileKind::NotFound => {}
+        FileKind::Error => {
+            report.skipped.push(Skipped {
+                path: to.to_path_buf(),
+                reason: SkipReason::CannotDetermine,
+            });
+            return Ok(false);
+        }
     }
 
-    Ok(())
+    Ok(true)
 }
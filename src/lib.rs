@@ -1,16 +1,52 @@
 mod add;
+mod apply;
+mod clean;
 mod config;
+mod config_check;
+mod config_file;
+mod config_migration;
+mod error;
 mod executor;
 mod file_collector;
 mod file_kind;
 mod file_operations;
+mod git;
+mod glob;
+mod hooks;
 mod install;
 mod list;
+mod permissions;
+mod plan;
 mod remove;
+mod report;
+mod revision;
+mod scripts;
+mod secret;
+mod secret_scan;
+mod state;
+mod status;
+mod sync;
 
 pub use add::*;
+pub use apply::*;
+pub use clean::clean;
 pub use config::*;
+pub use config_check::ConfigCheck;
+pub use config_file::LayeredConfig;
+pub use config_migration::{CONFIG_VERSION, ConfigMigration, migrate_config};
+pub use error::{Error, Result};
 pub use executor::*;
+pub use file_kind::FileKind;
+pub use hooks::*;
 pub use install::*;
 pub use list::*;
+pub use permissions::{ModeMismatch, Permission};
+pub use plan::*;
 pub use remove::*;
+pub use report::*;
+pub use revision::{
+    Revision, cached_revision, checkout_revision, checkout_revision_temporarily, prune_revisions,
+};
+pub use secret_scan::{SecretFinding, SecretKind};
+pub use status::*;
+pub use sync::*;
@@ -3,17 +3,33 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
 use clap::{Parser, Subcommand};
 
 use dotkoke::*;
 
+mod render;
+
+use render::*;
+
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     #[arg(long = "config", global = true)]
     config_file: Option<PathBuf>,
 
+    /// 設定ファイルの`[general] dotfiles`より優先する．環境変数`DOTKOKE_DOTFILES`でも指定できる．
+    #[arg(long = "dotfiles", value_name = "DIR", global = true)]
+    dotfiles_dir: Option<PathBuf>,
+
+    /// 設定ファイルの`[general] home`より優先する．環境変数`DOTKOKE_HOME`でも指定できる．
+    #[arg(long = "home", value_name = "DIR", global = true)]
+    home_dir: Option<PathBuf>,
+
+    /// 設定ファイルの`[general] backup_dir`より優先する．環境変数`DOTKOKE_BACKUP_DIR`でも指定できる．
+    #[arg(long = "backup-dir", value_name = "DIR", global = true)]
+    backup_dir: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -27,6 +43,22 @@ enum Command {
     Install {
         #[arg(long)]
         dry_run: bool,
+
+        /// 実際の操作は行わず，処理予定内容を計画としてJSONファイルに保存する．
+        #[arg(long, value_name = "PATH", conflicts_with = "dry_run")]
+        plan_out: Option<PathBuf>,
+
+        /// 作業ツリーではなく，指定したrevision(コミット，タグなど)時点のdotfiles/homeをinstallする．
+        #[arg(long)]
+        rev: Option<String>,
+    },
+
+    /// `install --plan-out`で保存した計画を適用する．
+    Apply {
+        #[arg(long)]
+        dry_run: bool,
+
+        plan: PathBuf,
     },
 
     /// `path`をdotfilesに加え管理対象に加える．
@@ -34,6 +66,14 @@ enum Command {
         #[arg(long)]
         dry_run: bool,
 
+        /// 暗号化してdotfilesに加える．
+        #[arg(long)]
+        encrypt: bool,
+
+        /// 秘密情報らしきものを含んでいても平文のまま加える．
+        #[arg(long)]
+        allow_secrets: bool,
+
         path: PathBuf,
     },
 
@@ -45,24 +85,137 @@ enum Command {
         path: PathBuf,
     },
 
-    /// unimplemented
-    Clean {},
+    /// dotfiles/home以下を指す壊れたリンクを$HOMEから削除する．
+    Clean {
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// dotfilesレポジトリをpullし，installとcleanを行う．
+    Sync {
+        #[arg(long)]
+        dry_run: bool,
+    },
 
     /// 管理対象ファイル一覧を表示する．
-    List {},
+    List {
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
 
-    /// unimplemented
-    Status {},
+    /// 管理対象ファイルのリンク状態を表示する．
+    Status {
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+
+    /// 設定ファイルを扱う．
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// `include`，`*.local.toml`を重ね合わせた設定と，各値を定義したファイルを表示する．
+    Show,
+
+    /// 設定ファイルを探す場所を優先順に表示し，実際に使われるものに`*`を付ける．
+    Path,
+
+    /// 設定を読み込み，ディレクトリが入れ子・重複していないか検査する．
+    Check,
+
+    /// 古い形式の設定ファイルを現在の形式で書き換える．移行前の内容は`<PATH>.bak`に保存する．
+    Migrate {
+        /// 書き換えずに移行後の内容を表示する．
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// configの探索候補．
+struct ConfigCandidate {
+    // 候補の由来(`--config`，`XDG_CONFIG_HOME`など)．
+    source: &'static str,
+
+    path: PathBuf,
+
+    // `--config`，`DOTKOKE_CONFIG`で明示的に指定されたもの．存在しなければエラーにする．
+    explicit: bool,
 }
 
-/// configを探す．
+/// configの探索候補を優先順に返す．
 ///
-/// 以下の優先順位でconfigを探す．
 /// 1. コマンドオプション`--config`で指定されたファイル
 /// 2. 環境変数`DOTKOKE_CONFIG`で指定されたファイル
-/// 3. `$HOME/.config/dotkoke_config.toml`
-/// 4. `$HOME/.config/dotkoke/dotkoke_config.toml`
-fn find_config_file_path(cli: &Cli) -> Result<PathBuf> {
+/// 3. `$XDG_CONFIG_HOME/dotkoke_config.toml`，`$XDG_CONFIG_HOME/dotkoke/dotkoke_config.toml`
+///    (`XDG_CONFIG_HOME`の既定値は`$HOME/.config`)
+/// 4. `$XDG_CONFIG_DIRS`の各ディレクトリについて3.と同様(`XDG_CONFIG_DIRS`の既定値は`/etc/xdg`)
+fn config_candidates(cli: &Cli) -> Vec<ConfigCandidate> {
+    // XDG Base Directoryの仕様に従い，相対パスは無視する．
+    fn xdg_dir(value: &str) -> Option<PathBuf> {
+        Some(PathBuf::from(value)).filter(|dir| dir.is_absolute())
+    }
+
+    let mut candidates = vec![];
+
+    if let Some(config) = &cli.config_file {
+        candidates.push(ConfigCandidate {
+            source: "--config",
+            path: config.clone(),
+            explicit: true,
+        });
+    }
+
+    if let Some(config) = env::var_os("DOTKOKE_CONFIG").filter(|config| !config.is_empty()) {
+        candidates.push(ConfigCandidate {
+            source: "DOTKOKE_CONFIG",
+            path: PathBuf::from(config),
+            explicit: true,
+        });
+    }
+
+    let config_home = env::var("XDG_CONFIG_HOME")
+        .ok()
+        .and_then(|dir| xdg_dir(&dir))
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|home| !home.is_empty())
+                .map(|home| PathBuf::from(home).join(".config"))
+        });
+    let config_dirs = env::var("XDG_CONFIG_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/etc/xdg".to_string())
+        .split(':')
+        .filter_map(xdg_dir)
+        .collect::<Vec<_>>();
+
+    let dirs = config_home
+        .into_iter()
+        .map(|dir| ("XDG_CONFIG_HOME", dir))
+        .chain(config_dirs.into_iter().map(|dir| ("XDG_CONFIG_DIRS", dir)));
+    for (source, dir) in dirs {
+        for path in [
+            dir.join("dotkoke_config.toml"),
+            dir.join("dotkoke/dotkoke_config.toml"),
+        ] {
+            candidates.push(ConfigCandidate {
+                source,
+                path,
+                explicit: false,
+            });
+        }
+    }
+
+    candidates
+}
+
+/// configを探す．探索順は`config_candidates`を参照．
+/// 明示的に指定されていない場合，見つからなければ`None`を返す．
+fn find_config_file_path(cli: &Cli) -> Result<Option<PathBuf>> {
     fn ensure_config_file(path: &Path) -> Result<()> {
         if !path.exists() {
             return Err(anyhow!("{} does not exist.", path.display()));
@@ -87,72 +240,345 @@ fn find_config_file_path(cli: &Cli) -> Result<PathBuf> {
         Ok(Some(path))
     }
 
-    if let Some(config) = &cli.config_file {
-        ensure_config_file(config)?;
-        return Ok(config.clone());
-    }
+    for ConfigCandidate { path, explicit, .. } in config_candidates(cli) {
+        if explicit {
+            ensure_config_file(&path)?;
+            return Ok(Some(path));
+        }
 
-    if let Some(config) = env::var("DOTKOKE_CONFIG").ok().map(PathBuf::from) {
-        ensure_config_file(&config)?;
-        return Ok(config);
+        if let Some(path) = resolve_optional_config(path)? {
+            return Ok(Some(path));
+        }
     }
 
-    // $HOME/.config/dotkoke_config.toml
-    if let Some(home) = env::var("HOME").ok().map(PathBuf::from) {
-        for config in [
-            home.join(".config/dotkoke_config.toml"),
-            home.join(".config/dotkoke/dotkoke_config.toml"),
-        ] {
-            if let Some(path) = resolve_optional_config(config)? {
-                return Ok(path);
-            }
+    Ok(None)
+}
+
+/// `[general]`の値を上書きするコマンドオプション・環境変数の値．
+struct GeneralOverride {
+    // `general.dotfiles`のような設定のキー．
+    key: &'static str,
+
+    // 値を指定したコマンドオプション(`--dotfiles`など)または環境変数(`DOTKOKE_DOTFILES`など)．
+    source: &'static str,
+
+    value: PathBuf,
+}
+
+/// コマンドオプション`--dotfiles`などと，環境変数`DOTKOKE_DOTFILES`など．コマンドオプションを優先する．
+/// 環境変数の値は設定ファイルの値と同様に`~`，`$VAR`，`${VAR}`を展開する．
+fn general_override_values(cli: &Cli) -> Result<Vec<GeneralOverride>> {
+    let options = [
+        (
+            "general.dotfiles",
+            "--dotfiles",
+            "DOTKOKE_DOTFILES",
+            &cli.dotfiles_dir,
+        ),
+        ("general.home", "--home", "DOTKOKE_HOME", &cli.home_dir),
+        (
+            "general.backup_dir",
+            "--backup-dir",
+            "DOTKOKE_BACKUP_DIR",
+            &cli.backup_dir,
+        ),
+    ];
+
+    let mut overrides = vec![];
+    for (key, option, env_key, arg) in options {
+        if let Some(value) = arg {
+            overrides.push(GeneralOverride {
+                key,
+                source: option,
+                value: value.clone(),
+            });
+        } else if let Some(value) = env::var_os(env_key).filter(|value| !value.is_empty()) {
+            let value = match value.to_str() {
+                Some(value) => expand_path(env_key, value)?,
+                None => PathBuf::from(value),
+            };
+            overrides.push(GeneralOverride {
+                key,
+                source: env_key,
+                value,
+            });
         }
     }
 
-    Err(anyhow!("config file not found."))
+    Ok(overrides)
+}
+
+fn general_overrides(overrides: &[GeneralOverride]) -> GeneralOverrides {
+    let value = |key: &str| {
+        overrides
+            .iter()
+            .find(|value| value.key == key)
+            .map(|value| value.value.clone())
+    };
+
+    GeneralOverrides {
+        dotfiles_dir: value("general.dotfiles"),
+        home_dir: value("general.home"),
+        backup_dir: value("general.backup_dir"),
+    }
+}
+
+/// `config path`．探索した候補を優先順に表示し，使われるものに`*`を付ける．
+fn print_config_candidates(cli: &Cli) -> Result<()> {
+    let found = find_config_file_path(cli);
+
+    let mut marked = false;
+    for ConfigCandidate { source, path, .. } in config_candidates(cli) {
+        let is_found = !marked
+            && found
+                .as_ref()
+                .is_ok_and(|found| found.as_ref() == Some(&path));
+        marked |= is_found;
+
+        let state = if path.is_file() {
+            "exists"
+        } else if path.exists() {
+            "not a file"
+        } else {
+            "not found"
+        };
+
+        println!(
+            "{} {} ({}, {})",
+            if is_found { "*" } else { " " },
+            path.display(),
+            source,
+            state
+        );
+    }
+
+    match found? {
+        Some(_) => Ok(()),
+        None => Err(anyhow!("config file not found.")),
+    }
+}
+
+/// `apply_planned`でpre-hookを挟んで計画を適用する．dry-runの場合は何も変更しない．
+fn run_planned<R: ChangedPaths>(
+    config: &Config,
+    event: HookEvent,
+    dry_run: bool,
+    plan: impl FnOnce(&PlanExecutor) -> dotkoke::Result<R>,
+) -> Result<R> {
+    let report = if dry_run {
+        apply_planned(&DryExecutor::new(config.clone()), event, dry_run, plan)
+    } else {
+        apply_planned(&RealExecutor::new(config.clone()), event, dry_run, plan)
+    }?;
+
+    Ok(report)
+}
+
+fn run_post_hook(
+    config: &Config,
+    event: HookEvent,
+    dry_run: bool,
+    report: &impl ChangedPaths,
+) -> Result<()> {
+    run_hook(config, Hook::post(event), &report.changed_paths(), dry_run)?;
+    Ok(())
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    if let Command::Config {
+        command: ConfigCommand::Path,
+    } = &cli.command
+    {
+        return print_config_candidates(&cli);
+    }
+
     let config_file_path = find_config_file_path(&cli)?;
 
-    let config = Config::read(config_file_path)?;
+    // 設定の検証に失敗しても確認できるよう，`Config::load`より前に処理する．
+    if let Command::Config {
+        command: command @ (ConfigCommand::Show | ConfigCommand::Migrate { .. }),
+    } = &cli.command
+    {
+        let config_file_path = config_file_path.ok_or_else(|| anyhow!("config file not found."))?;
+        match command {
+            ConfigCommand::Show => {
+                let layered = LayeredConfig::load(&config_file_path).with_context(|| {
+                    format!("failed to load config: {}", config_file_path.display())
+                })?;
+                print_warnings(&layered.warnings);
+                print_layered_config(&layered);
+
+                // コマンドオプション・環境変数の値は設定ファイルの値より優先される．
+                let overrides = general_override_values(&cli)?;
+                if !overrides.is_empty() {
+                    println!("\n# overridden by command line options or environment variables:");
+                    for GeneralOverride { key, source, value } in overrides {
+                        println!("# {} = {:?}  # {}", key, value, source);
+                    }
+                }
+            }
+            ConfigCommand::Migrate { dry_run } => {
+                let migrations = migrate_config(&config_file_path, *dry_run)
+                    .context("failed to migrate config")?;
+                print_config_migrations(&migrations, *dry_run);
+            }
+            ConfigCommand::Path => unreachable!("config path is handled before finding config"),
+            ConfigCommand::Check => unreachable!("config check is handled after loading config"),
+        }
+        return Ok(());
+    }
+
+    // dotfilesを`--dotfiles`などで指定すれば設定ファイルは無くてもよい．
+    let overrides = general_overrides(&general_override_values(&cli)?);
+    let config = match &config_file_path {
+        Some(path) => Config::load(Some(path), &overrides)
+            .with_context(|| format!("failed to load config: {}", path.display()))?,
+        None => Config::load(None, &overrides)?,
+    };
+    print_warnings(&config.warnings);
 
     match cli.command {
         Command::Init {} => {
             unimplemented!();
         }
-        Command::Install { dry_run } => {
-            if dry_run {
-                install(DryExecutor::new(config))?;
+        Command::Install {
+            dry_run,
+            plan_out,
+            rev,
+        } => {
+            // dry-runの場合は一時ディレクトリに取り出し，キャッシュには残さない．
+            let revision = match &rev {
+                Some(rev) => Some(
+                    if dry_run {
+                        checkout_revision_temporarily(&config, rev)
+                    } else {
+                        checkout_revision(&config, rev)
+                    }
+                    .with_context(|| format!("failed to check out revision {}", rev))?,
+                ),
+                None => None,
+            };
+            let worktree_config = config;
+            let config = match &revision {
+                Some(revision) => revision.config.clone(),
+                None => worktree_config.clone(),
+            };
+
+            // 計画の確認前に古いrevisionを削除すると，それを指すリンクが壊れるので削除しない．
+            if let Some(plan_out) = plan_out {
+                let executor = PlanExecutor::new(config);
+                let report = install(&executor).context("failed to make install plan")?;
+                print_warnings(&report.warnings);
+                let mut plan = executor.into_plan();
+                plan.rev = rev;
+                plan.write(&plan_out)
+                    .context("failed to save install plan")?;
+                println!("plan written: {}", plan_out.display());
             } else {
-                install(RealExecutor::new(config))?;
+                let report = run_planned(&config, HookEvent::Install, dry_run, |executor| {
+                    install(executor)
+                })
+                .context("install failed")?;
+                if !dry_run {
+                    prune_revisions(&worktree_config).context("failed to remove old revisions")?;
+                }
+                print_install_report(&report, dry_run);
+                run_post_hook(&config, HookEvent::Install, dry_run, &report)?;
             }
         }
-        Command::Add { path, dry_run } => {
-            if dry_run {
-                add(DryExecutor::new(config), path)?;
+        Command::Apply { plan, dry_run } => {
+            let plan_path = plan;
+            let plan = Plan::read(&plan_path)?;
+            // 計画のリンク元は`install --rev --plan-out`で取り出したもの．dry-runでは取り出し直さない．
+            let revision = match &plan.rev {
+                Some(rev) => Some(
+                    if dry_run {
+                        cached_revision(&config, rev)
+                    } else {
+                        checkout_revision(&config, rev)
+                    }
+                    .with_context(|| format!("failed to check out revision {}", rev))?,
+                ),
+                None => None,
+            };
+            let worktree_config = config;
+            let config = match &revision {
+                Some(revision) => revision.config.clone(),
+                None => worktree_config.clone(),
+            };
+            let report = if dry_run {
+                apply(&DryExecutor::new(config), &plan)
             } else {
-                add(RealExecutor::new(config), path)?;
+                apply(&RealExecutor::new(config), &plan)
+            }
+            .with_context(|| format!("failed to apply plan: {}", plan_path.display()))?;
+            if !dry_run {
+                prune_revisions(&worktree_config).context("failed to remove old revisions")?;
             }
+            print_apply_report(&report, dry_run);
+        }
+        Command::Add {
+            path,
+            dry_run,
+            encrypt,
+            allow_secrets,
+        } => {
+            let options = AddOptions {
+                encrypt,
+                allow_secrets,
+            };
+            let report = run_planned(&config, HookEvent::Add, dry_run, |executor| {
+                add(executor, &path, &options)
+            })
+            .with_context(|| format!("failed to add {}", path.display()))?;
+            print_add_report(&report, dry_run);
+            run_post_hook(&config, HookEvent::Add, dry_run, &report)?;
         }
         Command::Remove { path, dry_run } => {
-            if dry_run {
-                remove(DryExecutor::new(config), path)?;
+            let report = run_planned(&config, HookEvent::Remove, dry_run, |executor| {
+                remove(executor, &path)
+            })
+            .with_context(|| format!("failed to remove {}", path.display()))?;
+            print_remove_report(&report, dry_run);
+            run_post_hook(&config, HookEvent::Remove, dry_run, &report)?;
+        }
+        Command::Clean { dry_run } => {
+            let report = if dry_run {
+                clean(&DryExecutor::new(config))
             } else {
-                remove(RealExecutor::new(config), path)?;
+                clean(&RealExecutor::new(config))
             }
+            .context("clean failed")?;
+            print_clean_report(&report, dry_run);
         }
-        Command::Clean {} => {
-            unimplemented!();
+        Command::Sync { dry_run } => {
+            let report = if dry_run {
+                sync(&DryExecutor::new(config.clone()), dry_run)
+            } else {
+                sync(&RealExecutor::new(config.clone()), dry_run)
+            }
+            .context("sync failed")?;
+            print_sync_report(&report, dry_run);
+            // pre-installはsync内のinstallの前に実行される．
+            run_post_hook(&config, HookEvent::Install, dry_run, &report)?;
         }
-        Command::List {} => {
-            list(config)?;
+        Command::List { format } => {
+            let report = list(config).context("failed to list managed files")?;
+            print_list_report(&report, format)?;
         }
-        Command::Status {} => {
-            unimplemented!();
+        Command::Status { format } => {
+            let report = status(config).context("failed to get status")?;
+            print_status_report(&report, format)?;
+        }
+        Command::Config {
+            command: ConfigCommand::Check,
+        } => {
+            // エラーがあれば`Config::load`が失敗し，警告は上で表示済み．
+            println!("config is valid ({} warning(s)).", config.warnings.len());
         }
+        Command::Config { .. } => unreachable!("config command is handled before loading config"),
     }
 
     Ok(())
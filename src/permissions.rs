@@ -0,0 +1,101 @@
+//! `[permissions]`で指定したファイル・ディレクトリのmode．
+
+use std::{
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, Serializer};
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    glob::{glob_match, is_glob},
+};
+
+/// `pattern`($HOMEからの相対パスまたはglob)に一致するパスのmode．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permission {
+    pub pattern: String,
+    pub mode: u32,
+}
+
+impl Permission {
+    /// 設定ファイルの`"0700"`のような8進数の文字列からつくる．
+    pub(crate) fn parse(pattern: String, mode: &str) -> Result<Self> {
+        match u32::from_str_radix(mode, 8) {
+            Ok(parsed) if !mode.is_empty() && parsed <= 0o7777 => Ok(Permission {
+                pattern,
+                mode: parsed,
+            }),
+            _ => Err(Error::InvalidMode {
+                pattern,
+                mode: mode.to_string(),
+            }),
+        }
+    }
+}
+
+/// modeが指定と異なるパス．
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModeMismatch {
+    // $HOME側のパス
+    pub path: PathBuf,
+
+    #[serde(serialize_with = "serialize_mode")]
+    pub expected: u32,
+
+    #[serde(serialize_with = "serialize_mode")]
+    pub actual: u32,
+}
+
+fn serialize_mode<S: Serializer>(mode: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:04o}", mode))
+}
+
+/// `path`のmode(パーミッション部分のみ)．シンボリックリンクは辿る．
+pub(crate) fn mode_of(path: impl AsRef<Path>) -> Option<u32> {
+    fs::metadata(path)
+        .ok()
+        .map(|meta| meta.permissions().mode() & 0o7777)
+}
+
+/// `path`が誰かに実行可能ならtrue．シンボリックリンクは辿る．
+pub(crate) fn is_executable(path: impl AsRef<Path>) -> bool {
+    mode_of(path).is_some_and(|mode| mode & 0o111 != 0)
+}
+
+impl Config {
+    /// $HOME以下の`path`に指定されたmode．
+    ///
+    /// 複数のパターンに一致する場合は，globを含まないパターンを優先し，
+    /// 次に長いパターンを優先する．
+    pub fn mode_for(&self, path: impl AsRef<Path>) -> Option<u32> {
+        let rel = self.repo_rel_from_home(path).ok()?;
+
+        self.permissions
+            .iter()
+            .filter(|permission| glob_match(&permission.pattern, &rel))
+            .max_by_key(|permission| (!is_glob(&permission.pattern), permission.pattern.len()))
+            .map(|permission| permission.mode)
+    }
+
+    /// $HOME以下の管理対象ファイル`files`とその親ディレクトリ($HOME自身は除く)のうち，
+    /// modeが指定されているものを，ディレクトリ，ファイルの順に返す．
+    pub(crate) fn mode_targets(&self, files: &[PathBuf]) -> Vec<(PathBuf, u32)> {
+        let mut dirs = files
+            .iter()
+            .flat_map(|file| file.ancestors().skip(1))
+            .filter(|dir| dir.starts_with(&self.home_dir) && *dir != self.home_dir)
+            .map(Path::to_path_buf)
+            .collect::<Vec<_>>();
+        dirs.sort_unstable();
+        dirs.dedup();
+
+        dirs.iter()
+            .chain(files)
+            .filter_map(|path| Some((path.clone(), self.mode_for(path)?)))
+            .collect()
+    }
+}
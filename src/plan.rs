@@ -0,0 +1,108 @@
+//! ファイル操作の計画(plan)．
+//! JSONとして保存し，後から`apply`で適用できる．
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    executor::Operation,
+    file_kind::*,
+};
+
+/// 計画作成時点でのパスの状態．
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathState {
+    pub path: PathBuf,
+    pub kind: FileKind,
+
+    // シンボリックリンクの場合はリンク自体の更新時刻．
+    pub modified: Option<SystemTime>,
+}
+
+impl PathState {
+    /// `path`の現在の状態を取得する．
+    pub fn capture(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let modified = fs::symlink_metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok();
+
+        Self {
+            path: path.to_path_buf(),
+            kind: file_kind(path),
+            modified,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    pub operation: Operation,
+
+    // 操作が前提とするファイルの状態．
+    pub assumes: Vec<PathState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    pub home_dir: PathBuf,
+    pub dotfiles_home_dir: PathBuf,
+
+    // `install --rev`で作成した場合のrevision．
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+
+    pub steps: Vec<Step>,
+}
+
+impl Plan {
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+
+        let json = fs::read_to_string(path).map_err(|e| Error::io("read plan", path, e))?;
+
+        serde_json::from_str(&json).map_err(|source| Error::PlanParse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+
+        let json = serde_json::to_string_pretty(self).map_err(|source| Error::PlanSerialize {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        fs::write(path, json + "\n").map_err(|e| Error::io("write plan", path, e))
+    }
+
+    /// 各操作が前提とする状態が現在のファイルシステムと一致するか確認する．
+    ///
+    /// # NOTE
+    /// 計画作成時は実際の操作を行わないため，同じパスの状態はどのstepでも等しい．
+    /// したがって，適用前にすべてのstepの前提をまとめて確認できる．
+    pub fn verify(&self) -> Result<()> {
+        let mut changed = vec![];
+
+        for assumed in self.steps.iter().flat_map(|step| &step.assumes) {
+            let current = PathState::capture(&assumed.path);
+            if &current != assumed && !changed.contains(&assumed.path) {
+                changed.push(assumed.path.clone());
+            }
+        }
+
+        if !changed.is_empty() {
+            return Err(Error::Conflict { paths: changed });
+        }
+
+        Ok(())
+    }
+}
@@ -1,30 +1,56 @@
 use std::path::Path;
 
-use anyhow::{Context, Result, anyhow};
+use crate::{
+    error::{Error, Result},
+    executor::Executor,
+    file_kind::*,
+    git::{ensure_work_tree, is_tracked},
+    report::*,
+};
 
-use crate::{executor::Executor, file_kind::*};
+pub fn remove(executor: &impl Executor, path: impl AsRef<Path>) -> Result<RemoveReport> {
+    let mut report = RemoveReport::default();
 
-pub fn remove(executor: impl Executor, path: impl AsRef<Path>) -> Result<()> {
     let path = path.as_ref();
     let path = path
         .canonicalize()
-        .with_context(|| format!("invalid path: {}", path.display()))?;
-
-    if !path.starts_with(executor.dotfiles_home_dir()) {
-        return Err(anyhow!(
-            "{} is not in {}.",
-            path.display(),
-            executor.dotfiles_home_dir().display()
-        ));
+        .map_err(|e| Error::io("resolve path", path, e))?;
+
+    if executor.config().target_of_source(&path).is_none() {
+        return Err(Error::OutOfScope {
+            path,
+            scope: executor.dotfiles_home_dir().to_path_buf(),
+        });
     }
 
+    // 削除した後にコミットできないと分からないよう，先に確かめる．
+    // 一度もコミットしていないファイルは削除をコミットできないので，コミットしない．
+    let commit = if executor.auto_commit() {
+        ensure_work_tree(executor.dotfiles_dir())?;
+        is_tracked(executor.dotfiles_dir(), &path)
+    } else {
+        false
+    };
+
     let to = executor.install_path(&path)?;
 
     if is_symlink_pointing_to(&to, &path) || is_broken_link(&to) {
         executor.remove_symlink_from_home(&to)?;
+        report.removed_links.push(to);
+    }
+
+    executor.remove_file_from_dotfiles_home(&path)?;
+
+    if commit {
+        let message = format!(
+            "remove {}",
+            executor.repo_rel_from_dotfiles_home(&path)?.display()
+        );
+        executor.git_commit(std::slice::from_ref(&path), &message)?;
+        report.commit = Some(message);
     }
 
-    executor.remove_file_from_dotfiles_home(path)?;
+    report.removed_files.push(path);
 
-    Ok(())
+    Ok(report)
 }
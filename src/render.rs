@@ -0,0 +1,464 @@
+//! 各コマンドのreportを表示する．
+
+use std::path::Path;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use dotkoke::*;
+
+/// `list`，`status`の出力形式．
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum Format {
+    #[default]
+    Plain,
+    Json,
+    Tsv,
+}
+
+fn prefix(dry_run: bool) -> &'static str {
+    if dry_run { "[dry-run] " } else { "" }
+}
+
+pub fn print_warnings(warnings: &[String]) {
+    for warning in warnings {
+        eprintln!("[warning] {}", warning);
+    }
+}
+
+fn print_skipped(skipped: &[Skipped]) {
+    for Skipped { path, reason } in skipped {
+        match reason {
+            SkipReason::AlreadyLinked => {
+                println!("skipped (already linked): {}", path.display());
+            }
+            SkipReason::CannotDetermine => {
+                eprintln!(
+                    "[warning] cannot determine file kind of {} (skipped)",
+                    path.display()
+                );
+            }
+            SkipReason::Symlink => {
+                eprintln!("[warning] {} is a symlink. skipped.", path.display());
+            }
+            SkipReason::AlreadyExists => {
+                eprintln!("[warning] {} already exists. skipped.", path.display());
+            }
+            SkipReason::UpToDate => {
+                println!("skipped (already decrypted): {}", path.display());
+            }
+        }
+    }
+}
+
+fn print_commit(message: Option<&str>, dry_run: bool) {
+    if let Some(message) = message {
+        println!("{}committed: {}", prefix(dry_run), message);
+    }
+}
+
+fn print_backups(backups: &[PathPair], dry_run: bool) {
+    for PathPair { from, to } in backups {
+        println!(
+            "{}backed up: {} -> {}",
+            prefix(dry_run),
+            from.display(),
+            to.display()
+        );
+    }
+}
+
+pub fn print_install_report(report: &InstallReport, dry_run: bool) {
+    print_warnings(&report.warnings);
+
+    for path in &report.removed {
+        println!("{}removed: {}", prefix(dry_run), path.display());
+    }
+
+    for path in &report.created_dirs {
+        println!("{}created directory: {}", prefix(dry_run), path.display());
+    }
+
+    print_backups(&report.backups, dry_run);
+
+    for PathPair { from, to } in &report.created_links {
+        println!(
+            "{}created link: {} -> {}",
+            prefix(dry_run),
+            from.display(),
+            to.display()
+        );
+    }
+
+    for PathPair { from, to } in &report.decrypted {
+        println!(
+            "{}decrypted: {} -> {}",
+            prefix(dry_run),
+            from.display(),
+            to.display()
+        );
+    }
+
+    for ModeChange { path, mode } in &report.mode_changes {
+        println!(
+            "{}changed mode: {} ({:04o})",
+            prefix(dry_run),
+            path.display(),
+            mode
+        );
+    }
+
+    for path in &report.scripts {
+        println!("{}ran script: {}", prefix(dry_run), path.display());
+    }
+
+    for TriggerRun { command, .. } in &report.triggers {
+        println!("{}ran trigger: {}", prefix(dry_run), command);
+    }
+
+    print_skipped(&report.skipped);
+}
+
+pub fn print_add_report(report: &AddReport, dry_run: bool) {
+    if let Some(PathPair { from, to }) = &report.copied {
+        println!(
+            "{}{}: {} -> {}",
+            prefix(dry_run),
+            if report.encrypted {
+                "encrypted"
+            } else {
+                "copied"
+            },
+            from.display(),
+            to.display()
+        );
+    }
+
+    print_skipped(&report.skipped);
+
+    print_commit(report.commit.as_deref(), dry_run);
+}
+
+pub fn print_remove_report(report: &RemoveReport, dry_run: bool) {
+    for path in &report.removed_links {
+        println!("{}removed link: {}", prefix(dry_run), path.display());
+    }
+
+    for path in &report.removed_files {
+        println!("{}removed: {}", prefix(dry_run), path.display());
+    }
+
+    print_commit(report.commit.as_deref(), dry_run);
+}
+
+fn print_json(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// 1行1エントリで`repo_path`，`install_path`，`link_state`，`file_kind`をタブ区切りで表示する．
+fn print_tsv(entries: &[Entry]) {
+    for entry in entries {
+        println!(
+            "{}\t{}\t{}\t{}",
+            tsv_field(&entry.repo_path),
+            tsv_field(&entry.install_path),
+            entry.link_state.name(),
+            entry.file_kind.name()
+        );
+    }
+}
+
+/// パス中のタブ・改行で行が崩れないよう，`\`，タブ，CR，LFを`\\`，`\t`，`\r`，`\n`にエスケープする．
+fn tsv_field(path: &Path) -> String {
+    let mut field = String::new();
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '\\' => field.push_str("\\\\"),
+            '\t' => field.push_str("\\t"),
+            '\r' => field.push_str("\\r"),
+            '\n' => field.push_str("\\n"),
+            c => field.push(c),
+        }
+    }
+    field
+}
+
+pub fn print_clean_report(report: &CleanReport, dry_run: bool) {
+    print_warnings(&report.warnings);
+
+    for path in &report.removed_links {
+        println!("{}removed link: {}", prefix(dry_run), path.display());
+    }
+}
+
+pub fn print_sync_report(report: &SyncReport, dry_run: bool) {
+    if report.up_to_date {
+        println!("already up to date.");
+    }
+
+    for Change { kind, path } in &report.changes {
+        let kind = match kind {
+            ChangeKind::Added => "added",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Deleted => "deleted",
+            ChangeKind::Other => "changed",
+        };
+        println!("{}{:<9} {}", prefix(dry_run), kind, path.display());
+    }
+
+    print_install_report(&report.install, dry_run);
+
+    for path in &report.removed_links {
+        println!("{}removed link: {}", prefix(dry_run), path.display());
+    }
+
+    print_clean_report(&report.clean, dry_run);
+}
+
+pub fn print_list_report(report: &ListReport, format: Format) -> Result<()> {
+    print_warnings(&report.warnings);
+
+    match format {
+        Format::Plain => {
+            println!("managed file(s):");
+            for entry in &report.entries {
+                println!("  {}", entry.repo_path.display());
+            }
+        }
+        Format::Json => print_json(report)?,
+        Format::Tsv => print_tsv(&report.entries),
+    }
+
+    Ok(())
+}
+
+pub fn print_status_report(report: &StatusReport, format: Format) -> Result<()> {
+    print_warnings(&report.warnings);
+
+    match format {
+        Format::Plain => {
+            if let Some(repo) = &report.repo {
+                print_repo_status(repo);
+            }
+
+            for entry in &report.entries {
+                let modified = report
+                    .repo
+                    .as_ref()
+                    .is_some_and(|repo| repo.modified.contains(&entry.repo_path));
+                println!(
+                    "{:<13} {}{}",
+                    entry.link_state.name(),
+                    entry.install_path.display(),
+                    if modified { " (modified)" } else { "" }
+                );
+            }
+
+            for ModeMismatch {
+                path,
+                expected,
+                actual,
+            } in &report.mode_mismatches
+            {
+                println!(
+                    "{:<13} {} (expected {:04o}, actual {:04o})",
+                    "wrong_mode",
+                    path.display(),
+                    expected,
+                    actual
+                );
+            }
+        }
+        Format::Json => print_json(report)?,
+        Format::Tsv => print_tsv(&report.entries),
+    }
+
+    Ok(())
+}
+
+fn print_repo_status(repo: &RepoStatus) {
+    if repo.uncommitted_changes > 0 {
+        println!(
+            "dotfiles: {} uncommitted change(s)",
+            repo.uncommitted_changes
+        );
+    }
+
+    match repo.unpushed_commits {
+        Some(0) => {}
+        Some(count) => println!("dotfiles: {} unpushed commit(s)", count),
+        None => println!("dotfiles: no upstream branch"),
+    }
+}
+
+pub fn print_apply_report(report: &ApplyReport, dry_run: bool) {
+    for path in &report.created_dirs {
+        println!("{}created directory: {}", prefix(dry_run), path.display());
+    }
+
+    for operation in &report.performed {
+        let line = match operation {
+            Operation::CreateSymlink { from, to } => {
+                format!("created link: {} -> {}", from.display(), to.display())
+            }
+            Operation::Copy { from, to } => {
+                format!("copied: {} -> {}", from.display(), to.display())
+            }
+            Operation::RemoveFileFromHome { path } => {
+                match report.backups.iter().find(|backup| &backup.from == path) {
+                    Some(PathPair { from, to }) => {
+                        format!("backed up: {} -> {}", from.display(), to.display())
+                    }
+                    None => format!("removed: {}", path.display()),
+                }
+            }
+            Operation::DecryptToHome { from, to } => {
+                format!("decrypted: {} -> {}", from.display(), to.display())
+            }
+            Operation::EncryptToDotfilesHome { from, to } => {
+                format!("encrypted: {} -> {}", from.display(), to.display())
+            }
+            Operation::SetMode { path, mode } => {
+                format!("changed mode: {} ({:04o})", path.display(), mode)
+            }
+            Operation::RunScript { path } => format!("ran script: {}", path.display()),
+            Operation::RunTrigger { command, .. } => format!("ran trigger: {}", command),
+            Operation::GitCommit { message, .. } => format!("committed: {}", message),
+            Operation::GitFastForward => "fast-forwarded dotfiles".to_string(),
+            Operation::RemoveSymlinkFromHome { path }
+            | Operation::RemoveDirAllFromHome { path }
+            | Operation::RemoveUnknownPathFromHome { path }
+            | Operation::RemoveFileFromDotfilesHome { path } => {
+                format!("removed: {}", path.display())
+            }
+        };
+        println!("{}{}", prefix(dry_run), line);
+    }
+}
+
+/// `config show`．各値の後ろに，その値を定義したファイルをコメントとして表示する．
+pub fn print_layered_config(config: &LayeredConfig) {
+    for file in &config.files {
+        println!("# {}", file.display());
+    }
+    print_config_table(config, &config.table, &[]);
+}
+
+/// `keys`はトップレベルから`table`までのキー．
+fn print_config_table(config: &LayeredConfig, table: &toml::Table, keys: &[&str]) {
+    // `LayeredConfig::origins`のキー
+    let origin_key = |key: &str| {
+        keys.iter()
+            .copied()
+            .chain([key])
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+    let origin = |key: &str| {
+        config
+            .origin(&origin_key(key))
+            .map(|path| format!("  # {}", path.display()))
+            .unwrap_or_default()
+    };
+    // `[...]`に表示するキー
+    let header = |key: &str| {
+        keys.iter()
+            .copied()
+            .chain([key])
+            .map(toml_key)
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+
+    let is_table_array = |value: &toml::Value| {
+        value
+            .as_array()
+            .is_some_and(|array| !array.is_empty() && array.iter().all(toml::Value::is_table))
+    };
+
+    // 値，テーブル，テーブルの配列の順に表示する．
+    for (key, value) in table {
+        if !value.is_table() && !is_table_array(value) {
+            println!("{} = {}{}", toml_key(key), value, origin(key));
+        }
+    }
+
+    for (key, value) in table {
+        if let toml::Value::Table(table) = value {
+            println!("\n[{}]", header(key));
+            let keys = keys
+                .iter()
+                .copied()
+                .chain([key.as_str()])
+                .collect::<Vec<_>>();
+            print_config_table(config, table, &keys);
+        }
+    }
+
+    for (key, value) in table {
+        if is_table_array(value) {
+            for table in value.as_array().into_iter().flatten() {
+                println!("\n[[{}]]{}", header(key), origin(key));
+                for (key, value) in table.as_table().into_iter().flatten() {
+                    println!("{} = {}", toml_key(key), value);
+                }
+            }
+        }
+    }
+}
+
+/// `.ssh`のようにbare keyにできないキーは引用符で囲む．
+fn toml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
+
+pub fn print_config_migrations(migrations: &[ConfigMigration], dry_run: bool) {
+    if migrations.is_empty() {
+        println!("config is already version {}.", CONFIG_VERSION);
+        return;
+    }
+
+    for ConfigMigration {
+        path,
+        from,
+        content,
+        backup,
+    } in migrations
+    {
+        println!(
+            "{}migrated: {} (version {} -> {})",
+            prefix(dry_run),
+            path.display(),
+            from,
+            CONFIG_VERSION
+        );
+        if dry_run {
+            print!("{}", content);
+        } else {
+            println!("backed up: {}", backup.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tsv_field_escapes_separators() {
+        assert_eq!(tsv_field(Path::new("/home/a/.zshrc")), "/home/a/.zshrc");
+        assert_eq!(tsv_field(Path::new("/home/a\tb")), "/home/a\\tb");
+        assert_eq!(tsv_field(Path::new("/home/a\nb\r")), "/home/a\\nb\\r");
+        assert_eq!(tsv_field(Path::new("/home/a\\tb")), "/home/a\\\\tb");
+    }
+}
@@ -0,0 +1,198 @@
+//! 各コマンドの処理結果．
+//! 表示はCLI側で行う．
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::{executor::Operation, hooks::TriggerRun, status::Entry, sync::Change};
+
+/// `from`から`to`への対応(リンク，コピー，バックアップなど)．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPair {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    // 既に正しいリンクが貼られている．
+    AlreadyLinked,
+
+    // ファイルの種類が判別できない．
+    CannotDetermine,
+
+    // シンボリックリンクは管理対象にしない．
+    Symlink,
+
+    // 対応するパスに既にファイルが存在する．
+    AlreadyExists,
+
+    // 復号済みのファイルが既に最新の内容である．
+    UpToDate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+}
+
+/// `path`のmodeを`mode`に変更した．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModeChange {
+    pub path: PathBuf,
+    pub mode: u32,
+}
+
+/// フックに渡す，処理で変更された(dry-runの場合は変更される予定の)パス一覧．
+pub trait ChangedPaths {
+    fn changed_paths(&self) -> Vec<PathBuf>;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstallReport {
+    pub created_links: Vec<PathPair>,
+
+    // 暗号化されたファイル(`from`)を復号して`to`に書き込んだ．
+    pub decrypted: Vec<PathPair>,
+
+    pub skipped: Vec<Skipped>,
+
+    // `from`を`to`へ退避した．
+    pub backups: Vec<PathPair>,
+
+    // 退避のために作成したディレクトリ(存在しなかったbackup_dir)．
+    pub created_dirs: Vec<PathBuf>,
+
+    // 退避せずに削除した$HOME側のパス(シンボリックリンクやディレクトリなど)．
+    pub removed: Vec<PathBuf>,
+
+    // `[permissions]`に従ってmodeを変更した$HOME側のパス．
+    pub mode_changes: Vec<ModeChange>,
+
+    // 実行した`dotfiles/scripts/`以下のスクリプト．
+    pub scripts: Vec<PathBuf>,
+
+    // リンクを作成・復号したファイルに対して実行した`[triggers]`のコマンド．
+    pub triggers: Vec<TriggerRun>,
+
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddReport {
+    // $HOMEのファイル(`from`)をdotfiles/home以下(`to`)にコピーした．
+    pub copied: Option<PathPair>,
+
+    // `copied`が暗号化してコピーしたものである．
+    pub encrypted: bool,
+
+    pub skipped: Vec<Skipped>,
+
+    // `[git] auto_commit`でコミットした場合のコミットメッセージ．
+    pub commit: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoveReport {
+    // $HOME側から削除したリンク．
+    pub removed_links: Vec<PathBuf>,
+
+    // dotfiles/home以下から削除したファイル．
+    pub removed_files: Vec<PathBuf>,
+
+    // `[git] auto_commit`でコミットした場合のコミットメッセージ．
+    pub commit: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ListReport {
+    pub entries: Vec<Entry>,
+
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanReport {
+    // 削除した壊れたリンク．
+    pub removed_links: Vec<PathBuf>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    // upstreamと差分がなかった．
+    pub up_to_date: bool,
+
+    // upstreamで変更された管理対象ファイル．
+    pub changes: Vec<Change>,
+
+    pub install: InstallReport,
+
+    // 削除された管理対象ファイルを指していたリンク．
+    pub removed_links: Vec<PathBuf>,
+
+    // install後に行ったclean．
+    pub clean: CleanReport,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplyReport {
+    pub performed: Vec<Operation>,
+    pub backups: Vec<PathPair>,
+
+    // 退避のために作成したディレクトリ(存在しなかったbackup_dir)．
+    pub created_dirs: Vec<PathBuf>,
+}
+
+impl ChangedPaths for InstallReport {
+    /// $HOME側で変更されたパス．
+    fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut paths = self
+            .created_links
+            .iter()
+            .chain(&self.decrypted)
+            .map(|pair| pair.to.clone())
+            .chain(self.backups.iter().map(|pair| pair.from.clone()))
+            .chain(self.removed.iter().cloned())
+            .chain(self.mode_changes.iter().map(|change| change.path.clone()))
+            .collect::<Vec<_>>();
+        paths.sort_unstable();
+        paths.dedup();
+        paths
+    }
+}
+
+impl ChangedPaths for AddReport {
+    /// 取り込んだ$HOMEのファイルとdotfiles/home以下のコピー先．
+    fn changed_paths(&self) -> Vec<PathBuf> {
+        self.copied
+            .iter()
+            .flat_map(|pair| [pair.from.clone(), pair.to.clone()])
+            .collect()
+    }
+}
+
+impl ChangedPaths for RemoveReport {
+    fn changed_paths(&self) -> Vec<PathBuf> {
+        self.removed_links
+            .iter()
+            .chain(&self.removed_files)
+            .cloned()
+            .collect()
+    }
+}
+
+impl ChangedPaths for SyncReport {
+    /// installで変更されたパスと，削除したリンク．
+    fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut paths = self.install.changed_paths();
+        paths.extend(self.removed_links.iter().cloned());
+        paths.extend(self.clean.removed_links.iter().cloned());
+        paths.sort_unstable();
+        paths.dedup();
+        paths
+    }
+}
@@ -0,0 +1,299 @@
+//! dotfilesレポジトリの特定のrevisionからinstallするための，revision時点のファイルの取り出し．
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use tempfile::TempDir;
+
+use crate::{
+    clean::link_destination,
+    config::{Config, Link, Target},
+    error::{Error, Result},
+    file_kind::{FileKind, file_kind},
+    git::git,
+};
+
+/// `checkout_revision`で取り出したrevision．
+#[derive(Debug)]
+pub struct Revision {
+    // `dotfiles_home_dir`，各`[[target]]`の`source`，`[[link]]`の`src`を取り出したディレクトリ内にした設定．
+    pub config: Config,
+
+    // 一時的に取り出したディレクトリ．dropすると削除される．
+    _temporary: Option<TempDir>,
+}
+
+/// `rev`時点のdotfilesをキャッシュディレクトリ以下の`revisions/<コミット>`に取り出す．
+///
+/// インストールしたリンクはここを指すので，取り出したものは残しておく．既にあればそれを再利用する．
+/// 不要になったものは適用後に`prune_revisions`で削除する．
+pub fn checkout_revision(config: &Config, rev: &str) -> Result<Revision> {
+    let repo = config.dotfiles_dir.as_path();
+    let commit = resolve_commit(repo, rev)?;
+
+    let revisions = revisions_dir(config);
+    let dir = revisions.join(&commit);
+
+    if !dir.is_dir() {
+        // 途中で失敗したものを再利用しないよう，取り出し終えてから置く．
+        let partial = revisions.join(format!("{}.partial", commit));
+        if partial.exists() {
+            fs::remove_dir_all(&partial).map_err(|e| Error::io("remove", &partial, e))?;
+        }
+        extract(repo, &commit, &partial)?;
+        fs::rename(&partial, &dir).map_err(|e| Error::io_pair("rename", &partial, &dir, e))?;
+    }
+
+    Ok(Revision {
+        config: checked_out_config(config, &dir)?,
+        _temporary: None,
+    })
+}
+
+/// `checkout_revision`と同様だが，一時ディレクトリに取り出してdropで削除する．
+/// キャッシュディレクトリには触れないので，dry-runで使う．
+pub fn checkout_revision_temporarily(config: &Config, rev: &str) -> Result<Revision> {
+    let repo = config.dotfiles_dir.as_path();
+    let commit = resolve_commit(repo, rev)?;
+
+    // 他のユーザーが先に同名のディレクトリをつくれないよう，推測できない名前で安全に作成する．
+    let dir = tempfile::Builder::new()
+        .prefix("dotkoke-")
+        .tempdir()
+        .map_err(|e| Error::io("create temporary directory in", env::temp_dir(), e))?;
+
+    extract(repo, &commit, dir.path())?;
+
+    Ok(Revision {
+        config: checked_out_config(config, dir.path())?,
+        _temporary: Some(dir),
+    })
+}
+
+/// `checkout_revision`で取り出し済みの`rev`を使う．何も取り出さず，削除もしないので，dry-runで使う．
+pub fn cached_revision(config: &Config, rev: &str) -> Result<Revision> {
+    let commit = resolve_commit(&config.dotfiles_dir, rev)?;
+
+    let dir = revisions_dir(config).join(commit);
+    if !dir.is_dir() {
+        return Err(Error::NotADirectory { path: dir });
+    }
+
+    Ok(Revision {
+        config: checked_out_config(config, &dir)?,
+        _temporary: None,
+    })
+}
+
+fn resolve_commit(repo: &Path, rev: &str) -> Result<String> {
+    Ok(git(
+        repo,
+        ["rev-parse", "--verify", &format!("{}^{{commit}}", rev)],
+    )?
+    .trim()
+    .to_string())
+}
+
+/// `commit`時点のファイルを`git archive`で`dir`に書き出す．
+/// `git worktree`と異なり，レポジトリには何も記録しない．
+fn extract(repo: &Path, commit: &str, dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).map_err(|e| Error::io("create directory", dir, e))?;
+
+    let mut archive = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["archive", "--format=tar", commit])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::io("run git in", repo, e))?;
+
+    let tar = Command::new("tar")
+        .arg("-x")
+        .arg("-C")
+        .arg(dir)
+        .stdin(archive.stdout.take().expect("stdout should be piped"))
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| Error::io("run tar in", dir, e))?;
+
+    let archive = archive
+        .wait_with_output()
+        .map_err(|e| Error::io("run git in", repo, e))?;
+
+    let failure = if !archive.status.success() {
+        Some(archive.stderr)
+    } else if !tar.status.success() {
+        Some(tar.stderr)
+    } else {
+        None
+    };
+
+    if let Some(stderr) = failure {
+        let _ = fs::remove_dir_all(dir);
+        return Err(Error::Git {
+            repo: repo.to_path_buf(),
+            args: format!("archive --format=tar {} | tar -x", commit),
+            stderr: String::from_utf8_lossy(&stderr).trim().to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// `root`(レポジトリのルートを取り出したディレクトリ)内の対応するディレクトリ・ファイルを指す設定．
+fn checked_out_config(config: &Config, root: &Path) -> Result<Config> {
+    // gitレポジトリのルートからdotfilesへの相対パス(末尾に`/`が付く)．
+    let prefix = git(&config.dotfiles_dir, ["rev-parse", "--show-prefix"])?
+        .trim()
+        .to_string();
+    let root = root.join(prefix);
+
+    let checked_out = |key: &'static str, path: &Path| {
+        let rel = path
+            .strip_prefix(&config.dotfiles_dir)
+            .map_err(|_| Error::OutOfScope {
+                path: path.to_path_buf(),
+                scope: config.dotfiles_dir.clone(),
+            })?;
+        let path = root.join(rel);
+        path.canonicalize()
+            .map_err(|source| Error::InvalidConfigPath { key, path, source })
+    };
+
+    let targets = config
+        .targets
+        .iter()
+        .map(|target| {
+            Ok(Target {
+                source: checked_out("target.source", &target.source)?,
+                dest: target.dest.clone(),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    // `src`がrevisionに無い場合はinstall時に警告して無視されるので，存在は確かめない．
+    // dotfilesの外を指す`src`はそのままにする．
+    let links = config
+        .links
+        .iter()
+        .map(|link| Link {
+            src: link
+                .src
+                .strip_prefix(&config.dotfiles_dir)
+                .map(|rel| root.join(rel))
+                .unwrap_or_else(|_| link.src.clone()),
+            dst: link.dst.clone(),
+        })
+        .collect();
+
+    // `[[target]]`のみの場合はdotfiles/homeが存在しないことがある．
+    let dotfiles_home_dir = checked_out("dotfiles/home", &config.dotfiles_home_dir)
+        .unwrap_or_else(|_| root.join("home"));
+
+    Ok(Config {
+        dotfiles_home_dir,
+        targets,
+        links,
+        ..config.clone()
+    })
+}
+
+/// 取り出したrevisionと以前の`git worktree`による作業ツリーのうち，インストールしたリンクが指していないものを削除する．
+/// `config`は作業ツリーの設定(`Revision::config`ではないもの)．
+///
+/// # NOTE
+/// 計画の確認前や適用の失敗時にリンクを壊さないよう，適用に成功した後に呼ぶ．
+pub fn prune_revisions(config: &Config) -> Result<()> {
+    let worktrees = cache_dir(config).join("worktrees");
+    let dirs = [revisions_dir(config), worktrees.clone()]
+        .into_iter()
+        .filter(|dir| dir.is_dir())
+        .collect::<Vec<_>>();
+    // `--rev`を使っていなければdotfilesはgitレポジトリでなくてもよい．
+    if dirs.is_empty() {
+        return Ok(());
+    }
+
+    let prefix = git(&config.dotfiles_dir, ["rev-parse", "--show-prefix"])?
+        .trim()
+        .to_string();
+
+    for dir in dirs {
+        for entry in fs::read_dir(&dir).map_err(|e| Error::io("read_dir", &dir, e))? {
+            let path = entry
+                .map_err(|e| Error::io("read entry in", &dir, e))?
+                .path();
+            if !is_referenced(config, &path, &path.join(&prefix)) {
+                fs::remove_dir_all(&path).map_err(|e| Error::io("remove", &path, e))?;
+            }
+        }
+    }
+
+    if worktrees.is_dir() {
+        git(&config.dotfiles_dir, ["worktree", "prune"])?;
+        // 空になったら削除する．
+        let _ = fs::remove_dir(&worktrees);
+    }
+
+    Ok(())
+}
+
+/// 取り出したrevision`dir`(`root`はdotfilesに対応するディレクトリ)を指すリンクがあればtrue．
+fn is_referenced(config: &Config, dir: &Path, root: &Path) -> bool {
+    let points_into_dir =
+        |dest: &Path| link_destination(dest).is_some_and(|path| path.starts_with(dir));
+
+    if config.links.iter().any(|link| points_into_dir(&link.dst)) {
+        return true;
+    }
+
+    // 各`source`に対応するrevision内のパスについて，インストール先がそこを指すか確かめる．
+    let mut stack = config
+        .targets
+        .iter()
+        .filter_map(|target| target.source.strip_prefix(&config.dotfiles_dir).ok())
+        .map(|rel| root.join(rel))
+        .collect::<Vec<_>>();
+    while let Some(path) = stack.pop() {
+        let Some(dest) = path
+            .strip_prefix(root)
+            .ok()
+            .and_then(|rel| config.install_path(config.dotfiles_dir.join(rel)).ok())
+        else {
+            continue;
+        };
+        if points_into_dir(&dest) {
+            return true;
+        }
+
+        if file_kind(&path) == FileKind::Dir
+            && let Ok(entries) = fs::read_dir(&path)
+        {
+            stack.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path()),
+            );
+        }
+    }
+
+    false
+}
+
+/// 取り出したrevisionを置くディレクトリ．
+pub(crate) fn revisions_dir(config: &Config) -> PathBuf {
+    cache_dir(config).join("revisions")
+}
+
+/// `$XDG_CACHE_HOME/dotkoke`．未設定の場合は`$HOME/.cache/dotkoke`．
+fn cache_dir(config: &Config) -> PathBuf {
+    env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config.home_dir.join(".cache"))
+        .join("dotkoke")
+}
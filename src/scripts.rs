@@ -0,0 +1,127 @@
+//! `install`で実行する`dotfiles/scripts/`以下のスクリプトと，その実行状態．
+//!
+//! - `once-`で始まるスクリプトは，マシンごとに1回だけ実行する．
+//! - `onchange-`で始まるスクリプトは，初回と内容が変わったときに実行する．
+//!
+//! 実行状態は`state`の状態ファイルに保存する．
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    permissions::is_executable,
+    state::{State, content_hash, state_path},
+};
+
+const ONCE_PREFIX: &str = "once-";
+const ONCHANGE_PREFIX: &str = "onchange-";
+
+fn script_name(path: &Path) -> Option<&str> {
+    path.file_name()?.to_str()
+}
+
+/// `dir`以下のスクリプトのうち，実行すべきものを名前順に返す．
+/// `.`で始まるファイル(`.gitkeep`など)は無視し，
+/// `once-`，`onchange-`のどちらでも始まらないファイルと実行可能でないファイルは警告してスキップする．
+pub(crate) fn pending_scripts(
+    config: &Config,
+    dir: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let state = State::load(&state_path(config))?;
+
+    let mut scripts = fs::read_dir(dir)
+        .map_err(|e| Error::io("read_dir", dir, e))?
+        .map(|entry| {
+            entry
+                .map(|entry| entry.path())
+                .map_err(|e| Error::io("read entry in", dir, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    scripts.sort_unstable();
+
+    let mut pending = vec![];
+
+    for script in scripts {
+        if !script.is_file() {
+            continue;
+        }
+
+        let Some(name) = script_name(&script) else {
+            warnings.push(format!("{} has non-UTF-8 name. skipped.", script.display()));
+            continue;
+        };
+
+        if name.starts_with('.') {
+            continue;
+        }
+
+        if !is_executable(&script) {
+            warnings.push(format!("{} is not executable. skipped.", script.display()));
+            continue;
+        }
+
+        let last = state.scripts.get(name);
+
+        let run = if name.starts_with(ONCE_PREFIX) {
+            last.is_none()
+        } else if name.starts_with(ONCHANGE_PREFIX) {
+            let content = fs::read(&script).map_err(|e| Error::io("read", &script, e))?;
+            last != Some(&content_hash(&content))
+        } else {
+            warnings.push(format!(
+                "{} does not start with {} or {}. skipped.",
+                script.display(),
+                ONCE_PREFIX,
+                ONCHANGE_PREFIX
+            ));
+            continue;
+        };
+
+        if run {
+            pending.push(script);
+        }
+    }
+
+    Ok(pending)
+}
+
+/// `script`をdotfilesレポジトリのルートで実行し，成功したら実行状態を保存する．
+pub(crate) fn run_script(config: &Config, script: &Path) -> Result<()> {
+    // 計画(`apply`)から渡されたパスは`pending_scripts`を経ていない．
+    let name = script_name(script).ok_or_else(|| Error::InvalidScriptName {
+        path: script.to_path_buf(),
+    })?;
+
+    let content = fs::read(script).map_err(|e| Error::io("read", script, e))?;
+
+    let status = Command::new(script)
+        .current_dir(&config.dotfiles_dir)
+        .env("DOTKOKE_HOME", &config.home_dir)
+        .env("DOTKOKE_DOTFILES", &config.dotfiles_dir)
+        .status()
+        .map_err(|e| Error::io("run script", script, e))?;
+
+    if !status.success() {
+        return Err(Error::Script {
+            path: script.to_path_buf(),
+            status: status.to_string(),
+        });
+    }
+
+    let state_path = state_path(config);
+    let mut state = State::load(&state_path)?;
+    state
+        .scripts
+        .insert(name.to_string(), content_hash(&content));
+    state.save(&state_path)
+}
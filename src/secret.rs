@@ -0,0 +1,125 @@
+//! 暗号化されたファイルを外部コマンドで復号・暗号化する．
+
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    file_operations::write_private_file,
+    state::{DecryptedHashes, State, content_hash, state_path},
+};
+
+/// 暗号化されたファイルの接尾辞．
+pub(crate) const SECRET_SUFFIX: &str = ".enc";
+
+/// `path`の内容を標準入力として`sh -c command`を実行し，標準出力を返す．
+fn run_filter(command: &str, path: &Path) -> Result<Vec<u8>> {
+    let input = fs::read(path).map_err(|e| Error::io("read", path, e))?;
+
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::io("run command for", path, e))?;
+
+    // 子プロセスが入力を読み切らずに終了した場合の書き込みエラーは終了ステータスで判断する．
+    let _ = child
+        .stdin
+        .take()
+        .expect("stdin should be piped")
+        .write_all(&input);
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| Error::io("run command for", path, e))?;
+
+    if !output.status.success() {
+        return Err(Error::SecretCommand {
+            command: command.to_string(),
+            path: path.to_path_buf(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(output.stdout)
+}
+
+/// dotfiles/home以下の暗号化された`path`を復号した内容．
+fn decrypt(config: &Config, path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    let command =
+        config
+            .secrets
+            .decrypt_command
+            .as_deref()
+            .ok_or(Error::SecretCommandNotConfigured {
+                key: "decrypt_command",
+            })?;
+
+    run_filter(command, path.as_ref())
+}
+
+/// 暗号化された`from`を復号してmode 0600で`to`に書き込み，
+/// 暗号文と平文のハッシュを状態ファイルに記録する．
+pub(crate) fn decrypt_to(
+    config: &Config,
+    from: impl AsRef<Path>,
+    to: impl AsRef<Path>,
+) -> Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+
+    let source = fs::read(from).map_err(|e| Error::io("read", from, e))?;
+    let content = decrypt(config, from)?;
+    write_private_file(to, &content)?;
+
+    let state_path = state_path(config);
+    let mut state = State::load(&state_path)?;
+    state.secrets.insert(
+        to.to_string_lossy().into_owned(),
+        DecryptedHashes {
+            source: content_hash(&source),
+            content: content_hash(&content),
+        },
+    );
+    state.save(&state_path)
+}
+
+/// `to`が`from`を前回復号したときのままならtrue．
+/// 復号コマンドは実行せず，`decrypt_to`で記録したハッシュと比較する．
+pub(crate) fn is_decrypted_up_to_date(
+    config: &Config,
+    from: impl AsRef<Path>,
+    to: impl AsRef<Path>,
+) -> Result<bool> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+
+    let state = State::load(&state_path(config))?;
+    let Some(hashes) = state.secrets.get(to.to_string_lossy().as_ref()) else {
+        return Ok(false);
+    };
+
+    let hash_of = |path: &Path| fs::read(path).ok().map(|content| content_hash(&content));
+    Ok(hash_of(from).as_ref() == Some(&hashes.source)
+        && hash_of(to).as_ref() == Some(&hashes.content))
+}
+
+/// $HOME以下の`path`を暗号化した内容．
+pub(crate) fn encrypt(config: &Config, path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    let command =
+        config
+            .secrets
+            .encrypt_command
+            .as_deref()
+            .ok_or(Error::SecretCommandNotConfigured {
+                key: "encrypt_command",
+            })?;
+
+    run_filter(command, path.as_ref())
+}
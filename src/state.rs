@@ -0,0 +1,74 @@
+//! マシンごとの状態ファイル．
+//!
+//! `$XDG_STATE_HOME/dotkoke/state.json`(未設定の場合は`$HOME/.local/state/dotkoke/state.json`)に保存する．
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    file_operations::write_file,
+};
+
+/// 保存する状態．
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct State {
+    // スクリプトのファイル名 -> 最後に実行したときの内容のハッシュ
+    #[serde(default)]
+    pub scripts: BTreeMap<String, String>,
+
+    // 復号したファイルのインストール先 -> 復号したときの内容のハッシュ
+    #[serde(default)]
+    pub secrets: BTreeMap<String, DecryptedHashes>,
+}
+
+/// 復号したときの暗号文と平文のハッシュ．
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct DecryptedHashes {
+    pub source: String,
+    pub content: String,
+}
+
+impl State {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(State::default());
+        }
+
+        let json = fs::read_to_string(path).map_err(|e| Error::io("read state", path, e))?;
+
+        serde_json::from_str(&json).map_err(|source| Error::StateParse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).expect("state should be serializable");
+        write_file(path, (json + "\n").as_bytes())
+    }
+}
+
+/// `$XDG_STATE_HOME/dotkoke/state.json`．
+pub(crate) fn state_path(config: &Config) -> PathBuf {
+    env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config.home_dir.join(".local/state"))
+        .join("dotkoke")
+        .join("state.json")
+}
+
+/// 内容のハッシュ(64bit FNV-1a)．
+pub(crate) fn content_hash(content: &[u8]) -> String {
+    let hash = content.iter().fold(0xcbf29ce484222325u64, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,228 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    error::Result,
+    file_collector::*,
+    file_kind::*,
+    git::{git, is_work_tree},
+    permissions::{ModeMismatch, mode_of},
+    report::PathPair,
+};
+
+/// 管理対象ファイルのリンク状態．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkState {
+    // 正しくリンクが貼られている．
+    Linked,
+
+    // インストール先に何も存在しない．
+    NotInstalled,
+
+    // インストール先が別の場所を指すリンク(壊れたリンクを含む)．
+    WrongLink,
+
+    // インストール先にリンク以外のファイルやディレクトリが存在する．
+    Conflict,
+
+    // インストール先の状態が判別できない．
+    Unknown,
+
+    // 暗号化されたファイルの復号結果が通常ファイルとして存在する(内容は確認しない)．
+    Decrypted,
+}
+
+impl LinkState {
+    /// 機械可読な出力で使う名前．serdeでの名前と同じ．
+    pub fn name(&self) -> &'static str {
+        match self {
+            LinkState::Linked => "linked",
+            LinkState::NotInstalled => "not_installed",
+            LinkState::WrongLink => "wrong_link",
+            LinkState::Conflict => "conflict",
+            LinkState::Unknown => "unknown",
+            LinkState::Decrypted => "decrypted",
+        }
+    }
+
+    /// dotfiles/home以下の`from`のリンクが`to`に貼られているかを調べる．
+    pub fn of(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Self {
+        let to = to.as_ref();
+
+        if is_symlink_pointing_to(to, from) {
+            return LinkState::Linked;
+        }
+
+        match file_kind(to) {
+            FileKind::NotFound => LinkState::NotInstalled,
+            FileKind::Symlink => LinkState::WrongLink,
+            FileKind::File | FileKind::Dir | FileKind::Unknown => LinkState::Conflict,
+            FileKind::Error => LinkState::Unknown,
+        }
+    }
+}
+
+/// 管理対象ファイル1つ分の状態．
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entry {
+    // dotfiles/home以下のパス
+    pub repo_path: PathBuf,
+
+    // $HOME側のパス
+    pub install_path: PathBuf,
+
+    pub link_state: LinkState,
+
+    // $HOME側のパスのファイルの種類
+    pub file_kind: FileKind,
+}
+
+/// dotfilesレポジトリのgitの状態．
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RepoStatus {
+    // 未コミットの変更(未追跡ファイルを含む)の数．
+    pub uncommitted_changes: usize,
+
+    // upstreamにpushしていないコミットの数．
+    // upstreamが設定されていない場合はNone．
+    pub unpushed_commits: Option<usize>,
+
+    // HEADと比べて変更されている管理対象ファイル(dotfiles/home以下のパス)．
+    // コミットが無い場合は，ステージされた管理対象ファイルすべて．
+    pub modified: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StatusReport {
+    pub entries: Vec<Entry>,
+
+    // dotfilesがgitレポジトリでない場合はNone．
+    pub repo: Option<RepoStatus>,
+
+    // `[permissions]`の指定とmodeが異なるパス．
+    pub mode_mismatches: Vec<ModeMismatch>,
+
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+/// 管理対象ファイルとそのインストール先の状態を収集する．
+pub(crate) fn collect_entries(config: &Config) -> Result<(Vec<Entry>, Vec<String>)> {
+    let InstallPairs {
+        pairs, warnings, ..
+    } = collect_install_pairs(config)?;
+
+    let entries = pairs
+        .into_iter()
+        .map(|PathPair { from, to }| {
+            let link_state = if config.is_secret(&from) {
+                match file_kind(&to) {
+                    FileKind::File => LinkState::Decrypted,
+                    FileKind::NotFound => LinkState::NotInstalled,
+                    FileKind::Symlink => LinkState::WrongLink,
+                    FileKind::Dir | FileKind::Unknown => LinkState::Conflict,
+                    FileKind::Error => LinkState::Unknown,
+                }
+            } else {
+                LinkState::of(&from, &to)
+            };
+            Entry {
+                link_state,
+                file_kind: file_kind(&to),
+                repo_path: from,
+                install_path: to,
+            }
+        })
+        .collect();
+
+    Ok((entries, warnings))
+}
+
+fn repo_status(config: &Config) -> Result<Option<RepoStatus>> {
+    let repo = config.dotfiles_dir.as_path();
+
+    if !is_work_tree(repo) {
+        return Ok(None);
+    }
+
+    let uncommitted_changes = git(repo, ["status", "--porcelain"])?.lines().count();
+
+    // upstreamが無い場合はエラーになる．
+    let unpushed_commits = git(repo, ["rev-list", "--count", "@{upstream}..HEAD"])
+        .ok()
+        .and_then(|count| count.trim().parse().ok());
+
+    // コミットが1つも無い(HEADが未生成の)場合は空のツリーと比べる．
+    let base = match git(repo, ["rev-parse", "--verify", "--quiet", "HEAD"]) {
+        Ok(_) => "HEAD".to_string(),
+        Err(_) => git(repo, ["hash-object", "-t", "tree", "/dev/null"])?
+            .trim()
+            .to_string(),
+    };
+
+    let modified = git(
+        repo,
+        [
+            OsStr::new("diff"),
+            OsStr::new("--name-only"),
+            OsStr::new("--relative"),
+            OsStr::new("-z"),
+            OsStr::new(&base),
+            OsStr::new("--"),
+        ]
+        .into_iter()
+        .chain(
+            config
+                .targets
+                .iter()
+                .map(|target| target.source.as_os_str()),
+        )
+        .chain(config.links.iter().map(|link| link.src.as_os_str())),
+    )?
+    .split('\0')
+    .filter(|path| !path.is_empty())
+    .map(|path| repo.join(path))
+    .collect();
+
+    Ok(Some(RepoStatus {
+        uncommitted_changes,
+        unpushed_commits,
+        modified,
+    }))
+}
+
+pub fn status(config: Config) -> Result<StatusReport> {
+    let (entries, warnings) = collect_entries(&config)?;
+
+    let repo = repo_status(&config)?;
+
+    let install_paths = entries
+        .iter()
+        .map(|entry| entry.install_path.clone())
+        .collect::<Vec<_>>();
+    let mode_mismatches = config
+        .mode_targets(&install_paths)
+        .into_iter()
+        .filter_map(|(path, expected)| {
+            let actual = mode_of(&path)?;
+            (actual != expected).then_some(ModeMismatch {
+                path,
+                expected,
+                actual,
+            })
+        })
+        .collect();
+
+    Ok(StatusReport {
+        entries,
+        repo,
+        mode_mismatches,
+        warnings,
+    })
+}
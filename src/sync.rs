@@ -0,0 +1,163 @@
+use std::{ffi::OsStr, path::PathBuf};
+
+use crate::{
+    apply::apply_planned,
+    clean::{clean, link_destination},
+    error::{Error, Result},
+    executor::Executor,
+    file_kind::is_symlink,
+    git::git,
+    hooks::HookEvent,
+    install::install,
+    report::*,
+};
+
+/// dotfilesレポジトリの変更種別(`git diff --name-status`)．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Other,
+}
+
+/// pullで変更された管理対象ファイル．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub kind: ChangeKind,
+
+    // `[[target]]`の`source`以下のパスか，`[[link]]`の`src`．
+    pub path: PathBuf,
+}
+
+/// dotfilesレポジトリをupstreamに追従させ，installとcleanを行う．
+///
+/// 1. 未コミットの変更があればエラー．
+/// 1. `git fetch`し，upstreamにのみあるコミットで変更された管理対象ファイルを調べる．
+///    upstreamと分岐していればエラー．
+/// 1. upstreamへfast-forwardする．
+/// 1. `install`と同様に計画を作り，pre-installフックを実行してから適用する．
+///    フックが失敗した場合はinstall以降を行わない．
+/// 1. 削除された管理対象ファイル(`[[link]]`の`src`を含む)を指していたリンクを削除する．
+/// 1. `clean`する．
+///
+/// # NOTE
+/// dry-runの場合はfetchのみ行いfast-forwardしないため，
+/// installとcleanの処理予定内容は現在の作業ツリーに対するものになり，削除されるリンクも表示されない．
+/// `dry_run`はpre-installフックに渡す．
+pub fn sync(executor: &impl Executor, dry_run: bool) -> Result<SyncReport> {
+    let repo = executor.dotfiles_dir();
+
+    let status = git(repo, ["status", "--porcelain", "--untracked-files=no"])?;
+    if !status.trim().is_empty() {
+        return Err(Error::DirtyRepo {
+            repo: repo.to_path_buf(),
+            status: status.trim_end().to_string(),
+        });
+    }
+
+    git(repo, ["fetch", "--quiet"])?;
+
+    let count = |range: &str| -> Result<usize> {
+        Ok(git(repo, ["rev-list", "--count", range])?
+            .trim()
+            .parse()
+            .unwrap_or(0))
+    };
+    let incoming = count("HEAD..@{upstream}")?;
+    let outgoing = count("@{upstream}..HEAD")?;
+
+    if incoming > 0 && outgoing > 0 {
+        return Err(Error::DivergedRepo {
+            repo: repo.to_path_buf(),
+        });
+    }
+
+    // pushしていないコミットがあるだけならupstreamから取り込むものは無い．
+    let mut report = SyncReport {
+        up_to_date: incoming == 0,
+        ..Default::default()
+    };
+
+    if !report.up_to_date {
+        // upstreamにのみあるコミットでの変更(`HEAD...@{upstream}`はmerge-baseとupstreamの差分)．
+        let diff = git(
+            repo,
+            [
+                OsStr::new("diff"),
+                OsStr::new("--name-status"),
+                OsStr::new("--no-renames"),
+                OsStr::new("--relative"),
+                OsStr::new("-z"),
+                OsStr::new("HEAD...@{upstream}"),
+                OsStr::new("--"),
+            ]
+            .into_iter()
+            .chain(
+                executor
+                    .config()
+                    .targets
+                    .iter()
+                    .map(|target| target.source.as_os_str()),
+            )
+            .chain(
+                executor
+                    .config()
+                    .links
+                    .iter()
+                    .map(|link| link.src.as_os_str()),
+            ),
+        )?;
+
+        let mut fields = diff.split('\0').filter(|field| !field.is_empty());
+        while let (Some(kind), Some(path)) = (fields.next(), fields.next()) {
+            let kind = match kind {
+                "A" => ChangeKind::Added,
+                "M" => ChangeKind::Modified,
+                "D" => ChangeKind::Deleted,
+                _ => ChangeKind::Other,
+            };
+            report.changes.push(Change {
+                kind,
+                path: repo.join(path),
+            });
+        }
+
+        executor.git_fast_forward()?;
+    }
+
+    report.install = apply_planned(executor, HookEvent::Install, dry_run, |planner| {
+        install(planner)
+    })?;
+
+    // 削除されたファイルを指していたリンクを削除する．
+    for change in &report.changes {
+        // fast-forward後も残っているファイルを指すリンクは有効なので残す．
+        if change.kind != ChangeKind::Deleted || change.path.symlink_metadata().is_ok() {
+            continue;
+        }
+
+        // ミラーのインストール先と，`[[link]]`で宣言したインストール先．
+        let mut destinations = executor
+            .install_path(&change.path)
+            .ok()
+            .into_iter()
+            .collect::<Vec<_>>();
+        for link in &executor.config().links {
+            if link.src == change.path && !destinations.contains(&link.dst) {
+                destinations.push(link.dst.clone());
+            }
+        }
+
+        for to in destinations {
+            if is_symlink(&to) && link_destination(&to).as_ref() == Some(&change.path) {
+                executor.remove_symlink_from_home(&to)?;
+                report.removed_links.push(to);
+            }
+        }
+    }
+
+    report.clean = clean(executor)?;
+
+    Ok(report)
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use std::fs;
+
+use common::Fixture;
+use dotkoke::*;
+
+#[test]
+fn apply_records_created_backup_dir() {
+    let fixture = Fixture::new();
+    let zshrc = fixture.write_dotfile(".zshrc", "new");
+    let bashrc = fixture.write_dotfile(".bashrc", "new");
+    fixture.write_home(".zshrc", "old");
+    fixture.write_home(".bashrc", "old");
+
+    let config = fixture.config();
+    // 既定のbackup_dirのように，初めて退避するまで存在しない．
+    fs::remove_dir(&fixture.backup).unwrap();
+
+    let executor = PlanExecutor::new(config.clone());
+    let planned = install(&executor).unwrap();
+    assert_eq!(planned.created_dirs, vec![fixture.backup.clone()]);
+    let plan = executor.into_plan();
+
+    let report = apply(&RealExecutor::new(config.clone()), &plan).unwrap();
+
+    assert_eq!(report.created_dirs, vec![fixture.backup.clone()]);
+    assert_eq!(
+        report.backups,
+        vec![
+            PathPair {
+                from: fixture.home.join(".bashrc"),
+                to: config.backup_dir.join(".bashrc"),
+            },
+            PathPair {
+                from: fixture.home.join(".zshrc"),
+                to: config.backup_dir.join(".zshrc"),
+            },
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(config.backup_dir.join(".zshrc")).unwrap(),
+        "old"
+    );
+    assert_eq!(fs::read_link(fixture.home.join(".zshrc")).unwrap(), zshrc);
+    assert_eq!(fs::read_link(fixture.home.join(".bashrc")).unwrap(), bashrc);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use std::{fs, path::Path, process::Output};
+
+use common::{Fixture, commit_all, git};
+
+const AUTO_COMMIT: &str = "[git]\nauto_commit = true\n";
+
+/// ローカルの(リモートの無い)レポジトリでauto_commitを有効にしたfixture．
+fn local_repo() -> Fixture {
+    let fixture = Fixture::with_config(AUTO_COMMIT);
+    git(&fixture.dotfiles, ["init", "-q", "-b", "main"]);
+    fixture
+}
+
+fn run(fixture: &Fixture, args: &[&str]) -> Output {
+    let author = Path::new("dotkoke");
+    let email = Path::new("dotkoke@example.com");
+    fixture.run(
+        args,
+        &[
+            ("GIT_AUTHOR_NAME", author),
+            ("GIT_AUTHOR_EMAIL", email),
+            ("GIT_COMMITTER_NAME", author),
+            ("GIT_COMMITTER_EMAIL", email),
+        ],
+    )
+}
+
+fn assert_success(output: &Output) {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn log(fixture: &Fixture) -> String {
+    git(&fixture.dotfiles, ["log", "--format=%s"])
+}
+
+#[test]
+fn add_commits_added_file() {
+    let fixture = local_repo();
+    let zshrc = fixture.write_home(".zshrc", "zsh");
+
+    assert_success(&run(&fixture, &["add", zshrc.to_str().unwrap()]));
+
+    assert_eq!(log(&fixture), "add .zshrc\n");
+    assert_eq!(git(&fixture.dotfiles, ["status", "--porcelain"]), "");
+}
+
+#[test]
+fn remove_commits_removed_file() {
+    let fixture = local_repo();
+    let zshrc = fixture.write_dotfile(".zshrc", "zsh");
+    fixture.write_dotfile(".vimrc", "vim");
+    commit_all(&fixture.dotfiles, "first");
+
+    assert_success(&run(&fixture, &["remove", zshrc.to_str().unwrap()]));
+
+    assert_eq!(log(&fixture), "remove .zshrc\nfirst\n");
+    assert_eq!(git(&fixture.dotfiles, ["ls-files"]), "home/.vimrc\n");
+}
+
+#[test]
+fn remove_untracked_file_does_not_commit() {
+    let fixture = local_repo();
+    fixture.write_dotfile(".vimrc", "vim");
+    commit_all(&fixture.dotfiles, "first");
+    let zshrc = fixture.write_dotfile(".zshrc", "zsh");
+
+    let output = run(&fixture, &["remove", zshrc.to_str().unwrap()]);
+
+    assert_success(&output);
+    assert!(!zshrc.exists());
+    assert_eq!(log(&fixture), "first\n");
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("committed"));
+}
+
+#[test]
+fn auto_commit_outside_repo_fails_before_changing_files() {
+    let fixture = Fixture::with_config(AUTO_COMMIT);
+    let home_zshrc = fixture.write_home(".zshrc", "zsh");
+    let vimrc = fixture.write_dotfile(".vimrc", "vim");
+
+    let output = run(&fixture, &["add", home_zshrc.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("is not a git repository"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!fixture.dotfiles.join("home/.zshrc").exists());
+    assert_eq!(fs::read_to_string(&home_zshrc).unwrap(), "zsh");
+
+    let output = run(&fixture, &["remove", vimrc.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(vimrc.exists());
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use std::{fs, os::unix};
+
+use common::Fixture;
+use dotkoke::*;
+
+#[test]
+fn clean_removes_broken_links_in_mirrored_dirs() {
+    let fixture = Fixture::new();
+    fs::create_dir_all(fixture.dotfiles.join("home/.config/nvim")).unwrap();
+    let link = fixture.home.join(".config/nvim/init.lua");
+    fs::create_dir_all(link.parent().unwrap()).unwrap();
+    unix::fs::symlink(fixture.dotfiles.join("home/.config/nvim/init.lua"), &link).unwrap();
+
+    let report = clean(&RealExecutor::new(fixture.config())).unwrap();
+
+    assert_eq!(report.removed_links, vec![link.clone()]);
+    assert!(link.symlink_metadata().is_err());
+}
+
+#[test]
+fn clean_does_not_walk_unmirrored_dirs() {
+    let fixture = Fixture::new();
+    fixture.write_dotfile(".zshrc", "zsh");
+    // dotfiles/home/.cacheが無いので，~/.cache以下にインストールされたリンクは無い．
+    let link = fixture.home.join(".cache/tool/zshrc");
+    fs::create_dir_all(link.parent().unwrap()).unwrap();
+    unix::fs::symlink(fixture.dotfiles.join("home/.cache/tool/zshrc"), &link).unwrap();
+
+    let report = clean(&RealExecutor::new(fixture.config())).unwrap();
+
+    assert_eq!(report.removed_links, Vec::<std::path::PathBuf>::new());
+    assert!(link.symlink_metadata().is_ok());
+}
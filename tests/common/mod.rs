@@ -0,0 +1,149 @@
+//! 結合テスト用の一時ディレクトリ上のdotfilesレポジトリ・$HOME・設定ファイル．
+
+#![allow(dead_code)]
+
+use std::{
+    ffi::OsStr,
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use dotkoke::Config;
+use tempfile::TempDir;
+
+pub struct Fixture {
+    // dropすると削除される．
+    _root: TempDir,
+
+    pub root: PathBuf,
+    pub dotfiles: PathBuf,
+    pub home: PathBuf,
+    pub backup: PathBuf,
+    pub config_path: PathBuf,
+}
+
+impl Fixture {
+    /// `dotfiles/home`，`home`，`backup`と，それらを指す設定ファイルをつくる．
+    pub fn new() -> Self {
+        Self::with_config("")
+    }
+
+    /// `[general]`の後に`extra`を加えた設定ファイルでつくる．
+    pub fn with_config(extra: &str) -> Self {
+        let root_dir = TempDir::new().expect("create tempdir");
+        let root = root_dir
+            .path()
+            .canonicalize()
+            .expect("canonicalize tempdir");
+
+        let dotfiles = root.join("dotfiles");
+        let home = root.join("home");
+        let backup = root.join("backup");
+        for dir in [dotfiles.join("home"), home.clone(), backup.clone()] {
+            fs::create_dir_all(dir).expect("create dir");
+        }
+
+        let config_path = root.join("dotkoke_config.toml");
+        fs::write(
+            &config_path,
+            format!(
+                "[general]\ndotfiles = {:?}\nhome = {:?}\nbackup_dir = {:?}\n\n{}",
+                dotfiles, home, backup, extra
+            ),
+        )
+        .expect("write config");
+
+        Fixture {
+            _root: root_dir,
+            root,
+            dotfiles,
+            home,
+            backup,
+            config_path,
+        }
+    }
+
+    /// この設定ファイルでdotkokeを実行する．
+    /// 状態ファイルとキャッシュは`root`以下に置き，`envs`を環境変数に加える．
+    pub fn run(&self, args: &[&str], envs: &[(&str, &Path)]) -> Output {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_dotkoke"));
+        command
+            .arg("--config")
+            .arg(&self.config_path)
+            .args(args)
+            .env("XDG_STATE_HOME", self.root.join("state"))
+            .env("XDG_CACHE_HOME", self.root.join("cache"));
+        for key in [
+            "DOTKOKE_CONFIG",
+            "DOTKOKE_DOTFILES",
+            "DOTKOKE_HOME",
+            "DOTKOKE_BACKUP_DIR",
+        ] {
+            command.env_remove(key);
+        }
+        for (key, value) in envs {
+            command.env(key, value);
+        }
+        command.output().expect("run dotkoke")
+    }
+
+    pub fn config(&self) -> Config {
+        Config::read(&self.config_path).expect("read config")
+    }
+
+    /// `dotfiles/home/<rel>`に書き込み，そのパスを返す．
+    pub fn write_dotfile(&self, rel: &str, content: &str) -> PathBuf {
+        write(&self.dotfiles.join("home").join(rel), content)
+    }
+
+    /// `home/<rel>`に書き込み，そのパスを返す．
+    pub fn write_home(&self, rel: &str, content: &str) -> PathBuf {
+        write(&self.home.join(rel), content)
+    }
+}
+
+pub fn write(path: &Path, content: &str) -> PathBuf {
+    fs::create_dir_all(path.parent().expect("path should have parent")).expect("create dir");
+    fs::write(path, content).expect("write file");
+    path.to_path_buf()
+}
+
+/// 実行可能なシェルスクリプトを書き込む．
+pub fn write_script(path: &Path, body: &str) -> PathBuf {
+    write(path, &format!("#!/bin/sh\n{}\n", body));
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).expect("chmod");
+    path.to_path_buf()
+}
+
+/// `dir`で`git`を実行し，標準出力を返す．失敗したらpanicする．
+pub fn git<I, S>(dir: &Path, args: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("GIT_AUTHOR_NAME", "dotkoke")
+        .env("GIT_AUTHOR_EMAIL", "dotkoke@example.com")
+        .env("GIT_COMMITTER_NAME", "dotkoke")
+        .env("GIT_COMMITTER_EMAIL", "dotkoke@example.com")
+        .output()
+        .expect("run git");
+    assert!(
+        output.status.success(),
+        "git failed in {}: {}",
+        dir.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// `dir`の変更をすべてコミットする．
+pub fn commit_all(dir: &Path, message: &str) {
+    git(dir, ["add", "-A"]);
+    git(dir, ["commit", "-q", "-m", message]);
+}
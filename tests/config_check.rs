@@ -0,0 +1,181 @@
+mod common;
+
+use std::{fs, os::unix, path::Path};
+
+use common::Fixture;
+use dotkoke::*;
+
+const CONFIG_TARGET: &str = "[[target]]\nsource = \"config\"\ndest = \".config\"\n";
+
+#[test]
+fn nested_dest_without_shadowed_path_is_allowed() {
+    let fixture = Fixture::with_config(CONFIG_TARGET);
+    fs::create_dir_all(fixture.dotfiles.join("config")).unwrap();
+    fixture.write_dotfile(".zshrc", "");
+
+    let config = fixture.config();
+    assert!(config.check().errors.is_empty());
+    assert_eq!(
+        config
+            .entity_path(fixture.home.join(".config/nvim"))
+            .unwrap(),
+        fixture.dotfiles.join("config/nvim")
+    );
+}
+
+#[test]
+fn nested_dest_reachable_from_two_sources_is_rejected() {
+    let fixture = Fixture::with_config(CONFIG_TARGET);
+    fs::create_dir_all(fixture.dotfiles.join("config")).unwrap();
+    fixture.write_dotfile(".config/nvim/init.lua", "");
+
+    match Config::read(&fixture.config_path) {
+        Err(Error::InvalidDirectoryLayout { problems }) => assert_eq!(
+            problems,
+            vec![format!(
+                "install destination {} is reachable from both {} and {}.",
+                fixture.home.join(".config").display(),
+                fixture.dotfiles.join("home/.config").display(),
+                fixture.dotfiles.join("config").display()
+            )]
+        ),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn install_path_outside_dotfiles_is_error() {
+    let fixture = Fixture::new();
+    let config = fixture.config();
+
+    assert!(matches!(
+        config.install_path(fixture.home.join(".zshrc")),
+        Err(Error::OutOfScope { .. })
+    ));
+}
+
+#[test]
+fn link_dst_is_canonicalized_like_target_dest() {
+    let fixture =
+        Fixture::with_config("[[link]]\nsrc = \"shared/gitconfig\"\ndst = \"cfg/git/config\"\n");
+    fs::create_dir_all(fixture.home.join(".config/git")).unwrap();
+    unix::fs::symlink(fixture.home.join(".config"), fixture.home.join("cfg")).unwrap();
+
+    let config = fixture.config();
+    assert_eq!(config.links[0].dst, fixture.home.join(".config/git/config"));
+}
+
+/// 設定ファイルを`dotfiles`，`home`，`backup_dir`を指す現在の形式のものに書き換える．
+fn write_layout(fixture: &Fixture, dotfiles: &Path, home: &Path, backup_dir: &Path) {
+    for dir in [dotfiles.join("home").as_path(), home, backup_dir] {
+        fs::create_dir_all(dir).unwrap();
+    }
+    fs::write(
+        &fixture.config_path,
+        format!(
+            "version = 1\n\n[general]\ndotfiles = {:?}\nhome = {:?}\nbackup_dir = {:?}\n",
+            dotfiles, home, backup_dir
+        ),
+    )
+    .unwrap();
+}
+
+fn layout_problems(fixture: &Fixture) -> Vec<String> {
+    match Config::read(&fixture.config_path) {
+        Err(Error::InvalidDirectoryLayout { problems }) => problems,
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn backup_dir_inside_dotfiles_is_rejected() {
+    let fixture = Fixture::new();
+    let backup = fixture.dotfiles.join("backup");
+    write_layout(&fixture, &fixture.dotfiles, &fixture.home, &backup);
+
+    assert_eq!(
+        layout_problems(&fixture),
+        vec![format!(
+            "backup_dir {} is inside dotfiles {}.",
+            backup.display(),
+            fixture.dotfiles.display()
+        )]
+    );
+}
+
+#[test]
+fn dotfiles_inside_backup_dir_is_rejected() {
+    let fixture = Fixture::new();
+    let dotfiles = fixture.backup.join("dotfiles");
+    write_layout(&fixture, &dotfiles, &fixture.home, &fixture.backup);
+
+    assert_eq!(
+        layout_problems(&fixture),
+        vec![format!(
+            "dotfiles {} is inside backup_dir {}.",
+            dotfiles.display(),
+            fixture.backup.display()
+        )]
+    );
+}
+
+#[test]
+fn home_inside_dotfiles_home_is_rejected() {
+    let fixture = Fixture::new();
+    let home = fixture.dotfiles.join("home/user");
+    write_layout(&fixture, &fixture.dotfiles, &home, &fixture.backup);
+
+    let problems = layout_problems(&fixture);
+    assert!(
+        problems.contains(&format!(
+            "home {} is inside dotfiles {}.",
+            home.display(),
+            fixture.dotfiles.display()
+        )),
+        "{:?}",
+        problems
+    );
+    assert!(
+        problems.contains(&format!(
+            "install destination {} is inside {}.",
+            home.display(),
+            fixture.dotfiles.join("home").display()
+        )),
+        "{:?}",
+        problems
+    );
+}
+
+#[test]
+fn config_check_command_reports_result() {
+    let fixture = Fixture::new();
+    write_layout(&fixture, &fixture.dotfiles, &fixture.home, &fixture.backup);
+
+    let output = fixture.run(&["config", "check"], &[]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "config is valid (0 warning(s)).\n"
+    );
+
+    let backup = fixture.dotfiles.join("backup");
+    write_layout(&fixture, &fixture.dotfiles, &fixture.home, &backup);
+
+    let output = fixture.run(&["config", "check"], &[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("invalid directory layout in config:"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains(&format!(
+            "backup_dir {} is inside dotfiles {}.",
+            backup.display(),
+            fixture.dotfiles.display()
+        )),
+        "{}",
+        stderr
+    );
+}
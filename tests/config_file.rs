@@ -0,0 +1,111 @@
+mod common;
+
+use std::fs;
+
+use common::{Fixture, write};
+use dotkoke::*;
+use toml::Value;
+
+const MAIN: &str = "[permissions]\n\".ssh\" = \"0700\"\n\n\
+                    [[link]]\nsrc = \"shared/gitconfig\"\ndst = \".gitconfig\"\n";
+
+/// `MAIN`を加えた設定ファイルと，一部を上書きする`dotkoke_config.local.toml`．
+fn with_local() -> (Fixture, std::path::PathBuf) {
+    let fixture = Fixture::with_config(MAIN);
+    let other_home = fixture.root.join("other_home");
+    fs::create_dir_all(&other_home).unwrap();
+    let local = write(
+        &fixture.root.join("dotkoke_config.local.toml"),
+        &format!(
+            "[general]\nhome = {:?}\n\n[permissions]\n\".gnupg\" = \"0700\"\n\n\
+             [[link]]\nsrc = \"shared/npmrc\"\ndst = \".npmrc\"\n",
+            other_home
+        ),
+    );
+    (fixture, local)
+}
+
+#[test]
+fn local_config_overrides_key_by_key() {
+    let (fixture, local) = with_local();
+
+    let layered = LayeredConfig::load(&fixture.config_path).unwrap();
+
+    assert_eq!(
+        layered.files,
+        vec![fixture.config_path.clone(), local.clone()]
+    );
+    assert_eq!(layered.origin("general.home"), Some(local.as_path()));
+    assert_eq!(
+        layered.origin("general.dotfiles"),
+        Some(fixture.config_path.as_path())
+    );
+    // テーブルはキーごとに重ね合わせる．
+    assert_eq!(
+        layered.origin("permissions..ssh"),
+        Some(fixture.config_path.as_path())
+    );
+    assert_eq!(layered.origin("permissions..gnupg"), Some(local.as_path()));
+    assert_eq!(layered.table["permissions"].as_table().unwrap().len(), 2);
+
+    // 配列は値全体を置き換える．
+    let links = layered.table["link"].as_array().unwrap();
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0]["src"], Value::String("shared/npmrc".to_string()));
+    assert_eq!(layered.origin("link"), Some(local.as_path()));
+
+    let config = fixture.config();
+    assert_eq!(config.home_dir, fixture.root.join("other_home"));
+    assert_eq!(config.links.len(), 1);
+    assert_eq!(config.links[0].src, fixture.dotfiles.join("shared/npmrc"));
+}
+
+#[test]
+fn config_show_prints_origins() {
+    let (fixture, local) = with_local();
+
+    let output = fixture.run(&["config", "show"], &[]);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in [
+        format!("# {}", fixture.config_path.display()),
+        format!("# {}", local.display()),
+        format!(
+            "dotfiles = {:?}  # {}",
+            fixture.dotfiles,
+            fixture.config_path.display()
+        ),
+        format!(
+            "home = {:?}  # {}",
+            fixture.root.join("other_home"),
+            local.display()
+        ),
+        format!("\".ssh\" = \"0700\"  # {}", fixture.config_path.display()),
+        format!("\".gnupg\" = \"0700\"  # {}", local.display()),
+        format!("[[link]]  # {}", local.display()),
+    ] {
+        assert!(
+            stdout.lines().any(|l| l == line),
+            "{:?} not in\n{}",
+            line,
+            stdout
+        );
+    }
+}
+
+#[test]
+fn include_cycle_is_error() {
+    let fixture = Fixture::new();
+    let a = write(&fixture.root.join("a.toml"), "include = \"b.toml\"\n");
+    write(&fixture.root.join("b.toml"), "include = \"a.toml\"\n");
+
+    match LayeredConfig::load(&a) {
+        Err(Error::ConfigIncludeCycle { path }) => assert_eq!(path, a),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use std::fs;
+
+use common::{Fixture, write};
+use dotkoke::*;
+
+#[test]
+fn unversioned_file_has_no_version_origin() {
+    let fixture = Fixture::new();
+    let layered = LayeredConfig::load(&fixture.config_path).unwrap();
+
+    assert_eq!(layered.origin("version"), None);
+    assert_eq!(
+        layered.origin("general.home"),
+        Some(fixture.config_path.as_path())
+    );
+}
+
+#[test]
+fn migrate_backs_up_original() {
+    let fixture = Fixture::new();
+    let original = fs::read_to_string(&fixture.config_path).unwrap();
+
+    let migrations = migrate_config(&fixture.config_path, false).unwrap();
+
+    assert_eq!(migrations.len(), 1);
+    assert_eq!(fs::read_to_string(&migrations[0].backup).unwrap(), original);
+    assert_eq!(
+        fs::read_to_string(&fixture.config_path).unwrap(),
+        format!("version = {}\n\n{}", CONFIG_VERSION, original)
+    );
+}
+
+#[test]
+fn migrate_refuses_to_overwrite_backup() {
+    let fixture = Fixture::new();
+    let common = write(&fixture.root.join("common.toml"), "[permissions]\n");
+    let config = fs::read_to_string(&fixture.config_path).unwrap();
+    write(
+        &fixture.config_path,
+        &format!("include = \"common.toml\"\n\n{}", config),
+    );
+    let backup = write(
+        &fixture.root.join("dotkoke_config.toml.bak"),
+        "old backup\n",
+    );
+    let original = fs::read_to_string(&fixture.config_path).unwrap();
+    assert!(
+        LayeredConfig::load(&fixture.config_path)
+            .unwrap()
+            .files
+            .contains(&common)
+    );
+
+    match migrate_config(&fixture.config_path, false) {
+        Err(Error::MigrationBackupExists { path }) => assert_eq!(path, backup),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // どのファイルも書き換えない．
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "old backup\n");
+    assert_eq!(fs::read_to_string(&fixture.config_path).unwrap(), original);
+    assert_eq!(fs::read_to_string(&common).unwrap(), "[permissions]\n");
+    assert!(!fixture.root.join("common.toml.bak").exists());
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use common::{Fixture, write};
+
+/// `--config`を指定せずに，`fixture.root`をカレントディレクトリ，`fixture.root/user`を$HOMEとして実行する．
+fn run_without_config(fixture: &Fixture, args: &[&str], envs: &[(&str, &str)]) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_dotkoke"));
+    command
+        .args(args)
+        .current_dir(&fixture.root)
+        .env("HOME", fixture.root.join("user"))
+        .env("XDG_STATE_HOME", fixture.root.join("state"))
+        .env("XDG_CACHE_HOME", fixture.root.join("cache"));
+    for key in [
+        "XDG_CONFIG_HOME",
+        "XDG_CONFIG_DIRS",
+        "DOTKOKE_CONFIG",
+        "DOTKOKE_DOTFILES",
+        "DOTKOKE_HOME",
+        "DOTKOKE_BACKUP_DIR",
+    ] {
+        command.env_remove(key);
+    }
+    for (key, value) in envs {
+        command.env(key, value);
+    }
+    command.output().expect("run dotkoke")
+}
+
+/// `fixture`の設定ファイルを`path`に複製する．
+fn copy_config(fixture: &Fixture, path: &Path) -> PathBuf {
+    write(path, &fs::read_to_string(&fixture.config_path).unwrap())
+}
+
+fn success_stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn config_path_lists_candidates_and_marks_xdg_config_home() {
+    let fixture = Fixture::new();
+    let config_home = fixture.root.join("xdg_home");
+    let config_dir = fixture.root.join("xdg_dir");
+    let nested = copy_config(&fixture, &config_home.join("dotkoke/dotkoke_config.toml"));
+    let system = copy_config(&fixture, &config_dir.join("dotkoke_config.toml"));
+
+    let output = run_without_config(
+        &fixture,
+        &["config", "path"],
+        &[
+            ("XDG_CONFIG_HOME", config_home.to_str().unwrap()),
+            ("XDG_CONFIG_DIRS", config_dir.to_str().unwrap()),
+        ],
+    );
+
+    assert_eq!(
+        success_stdout(&output),
+        format!(
+            "  {} (XDG_CONFIG_HOME, not found)\n\
+             * {} (XDG_CONFIG_HOME, exists)\n  \
+             {} (XDG_CONFIG_DIRS, exists)\n  \
+             {} (XDG_CONFIG_DIRS, not found)\n",
+            config_home.join("dotkoke_config.toml").display(),
+            nested.display(),
+            system.display(),
+            config_dir.join("dotkoke/dotkoke_config.toml").display(),
+        )
+    );
+}
+
+#[test]
+fn xdg_config_dirs_are_searched_in_order() {
+    let fixture = Fixture::new();
+    let first = fixture.root.join("first");
+    let second = fixture.root.join("second");
+    let config = copy_config(&fixture, &second.join("dotkoke_config.toml"));
+    let dirs = format!("{}:{}", first.display(), second.display());
+
+    let output = run_without_config(&fixture, &["config", "path"], &[("XDG_CONFIG_DIRS", &dirs)]);
+
+    let stdout = success_stdout(&output);
+    assert!(
+        stdout.contains(&format!("* {} (XDG_CONFIG_DIRS, exists)", config.display())),
+        "{}",
+        stdout
+    );
+    // $XDG_CONFIG_HOMEが未設定なら$HOME/.configを探す．
+    assert!(
+        stdout.starts_with(&format!(
+            "  {} (XDG_CONFIG_HOME, not found)",
+            fixture
+                .root
+                .join("user/.config/dotkoke_config.toml")
+                .display()
+        )),
+        "{}",
+        stdout
+    );
+
+    // 見つかった設定ファイルが使われる．
+    let output = run_without_config(
+        &fixture,
+        &["config", "check"],
+        &[("XDG_CONFIG_DIRS", &dirs)],
+    );
+    success_stdout(&output);
+}
+
+#[test]
+fn relative_xdg_dirs_are_ignored() {
+    let fixture = Fixture::new();
+    copy_config(&fixture, &fixture.root.join("relative/dotkoke_config.toml"));
+    let home_config = copy_config(
+        &fixture,
+        &fixture.root.join("user/.config/dotkoke_config.toml"),
+    );
+
+    let output = run_without_config(
+        &fixture,
+        &["config", "path"],
+        &[
+            ("XDG_CONFIG_HOME", "relative"),
+            ("XDG_CONFIG_DIRS", "relative:/nonexistent"),
+        ],
+    );
+
+    let stdout = success_stdout(&output);
+    assert!(!stdout.contains("relative"), "{}", stdout);
+    assert!(
+        stdout.contains(&format!(
+            "* {} (XDG_CONFIG_HOME, exists)",
+            home_config.display()
+        )),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("/nonexistent/dotkoke_config.toml (XDG_CONFIG_DIRS, not found)"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn config_path_without_config_is_error() {
+    let fixture = Fixture::new();
+
+    let output = run_without_config(
+        &fixture,
+        &["config", "path"],
+        &[("XDG_CONFIG_DIRS", "/nonexistent")],
+    );
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("config file not found."));
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use std::fs;
+
+use common::{Fixture, write, write_script};
+
+#[test]
+fn pre_hook_sees_planned_paths_and_plan_is_applied() {
+    let fixture = Fixture::new();
+    let zshrc = fixture.write_dotfile(".zshrc", "zsh");
+    let log = fixture.root.join("hook.log");
+    write_script(
+        &fixture.dotfiles.join("hooks/pre-install"),
+        &format!(
+            "echo \"$DOTKOKE_DRY_RUN $DOTKOKE_CHANGED_PATHS\" >> {:?}",
+            log
+        ),
+    );
+
+    let output = fixture.run(&["install"], &[]);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(&log).unwrap(),
+        format!("0 {}\n", fixture.home.join(".zshrc").display())
+    );
+    assert_eq!(fs::read_link(fixture.home.join(".zshrc")).unwrap(), zshrc);
+}
+
+#[test]
+fn pre_hook_changing_planned_files_aborts() {
+    let fixture = Fixture::new();
+    fixture.write_dotfile(".zshrc", "zsh");
+    write_script(
+        &fixture.dotfiles.join("hooks/pre-install"),
+        &format!("echo hook > {:?}", fixture.home.join(".zshrc")),
+    );
+
+    let output = fixture.run(&["install"], &[]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("file state has changed"));
+    assert_eq!(
+        fs::read_to_string(fixture.home.join(".zshrc")).unwrap(),
+        "hook\n"
+    );
+}
+
+#[test]
+fn non_executable_hook_file_is_error() {
+    let fixture = Fixture::new();
+    fixture.write_dotfile(".zshrc", "zsh");
+    write(&fixture.dotfiles.join("hooks/pre-install"), "#!/bin/sh\n");
+
+    let output = fixture.run(&["install"], &[]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is not executable"));
+    assert!(fixture.home.join(".zshrc").symlink_metadata().is_err());
+}
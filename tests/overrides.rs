@@ -0,0 +1,83 @@
+mod common;
+
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+use common::Fixture;
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn env_override_is_expanded() {
+    let fixture = Fixture::new();
+    let output = fixture.run(
+        &["config", "show"],
+        &[
+            ("DOTKOKE_HOME", Path::new("$FIXTURE_ROOT/other")),
+            ("FIXTURE_ROOT", &fixture.root),
+        ],
+    );
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!(
+            "# general.home = {:?}  # DOTKOKE_HOME",
+            fixture.root.join("other")
+        )),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn env_override_with_undefined_variable_is_error() {
+    let fixture = Fixture::new();
+    let output = fixture.run(
+        &["status"],
+        &[("DOTKOKE_HOME", Path::new("$DOTKOKE_TEST_UNDEFINED/home"))],
+    );
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains(
+            "DOTKOKE_HOME refers to undefined environment variable $DOTKOKE_TEST_UNDEFINED"
+        ),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn load_error_without_config_file_is_not_masked() {
+    let fixture = Fixture::new();
+    let output = Command::new(env!("CARGO_BIN_EXE_dotkoke"))
+        .arg("status")
+        .env("HOME", fixture.root.join("nohome"))
+        .env("XDG_CONFIG_HOME", fixture.root.join("noconfig"))
+        .env("XDG_CONFIG_DIRS", fixture.root.join("noconfig"))
+        .env("XDG_STATE_HOME", fixture.root.join("state"))
+        .env("XDG_CACHE_HOME", fixture.root.join("cache"))
+        .env_remove("DOTKOKE_CONFIG")
+        .env_remove("DOTKOKE_DOTFILES")
+        .env_remove("DOTKOKE_HOME")
+        .env_remove("DOTKOKE_BACKUP_DIR")
+        .output()
+        .expect("run dotkoke");
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("dotfiles directory is not configured."),
+        "{}",
+        stderr(&output)
+    );
+    assert!(
+        !stderr(&output).contains("config file not found."),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use std::{fs, os::unix::fs::PermissionsExt, path::Path};
+
+use common::Fixture;
+use dotkoke::*;
+
+const SSH_PERMISSIONS: &str = "[permissions]\n\".ssh\" = \"0700\"\n\".ssh/*\" = \"0600\"\n";
+
+fn mode(path: &Path) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o7777
+}
+
+#[test]
+fn install_sets_modes_of_created_parent_dirs_and_files() {
+    let fixture = Fixture::with_config(SSH_PERMISSIONS);
+    let config = fixture.write_dotfile(".ssh/config", "Host *");
+    fs::set_permissions(&config, fs::Permissions::from_mode(0o644)).unwrap();
+
+    install(&RealExecutor::new(fixture.config())).unwrap();
+
+    assert_eq!(mode(&fixture.home.join(".ssh")), 0o700);
+    // リンクの場合はリンク元のmodeを変える．
+    assert_eq!(
+        fs::read_link(fixture.home.join(".ssh/config")).unwrap(),
+        config
+    );
+    assert_eq!(mode(&config), 0o600);
+}
+
+#[test]
+fn decrypted_secret_defaults_to_0600() {
+    let fixture = Fixture::with_config("[secrets]\ndecrypt_command = \"cat\"\n");
+    fixture.write_dotfile(".netrc.enc", "machine example.com");
+
+    install(&RealExecutor::new(fixture.config())).unwrap();
+
+    let netrc = fixture.home.join(".netrc");
+    assert_eq!(fs::read_to_string(&netrc).unwrap(), "machine example.com");
+    assert_eq!(mode(&netrc), 0o600);
+}
+
+#[test]
+fn status_reports_mode_mismatches() {
+    let fixture = Fixture::with_config(SSH_PERMISSIONS);
+    fixture.write_dotfile(".ssh/config", "Host *");
+    install(&RealExecutor::new(fixture.config())).unwrap();
+    let ssh = fixture.home.join(".ssh");
+    fs::set_permissions(&ssh, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let report = status(fixture.config()).unwrap();
+
+    assert_eq!(
+        report.mode_mismatches,
+        vec![ModeMismatch {
+            path: ssh,
+            expected: 0o700,
+            actual: 0o755,
+        }]
+    );
+}
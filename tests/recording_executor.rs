@@ -0,0 +1,120 @@
+mod common;
+
+use std::fs;
+
+use common::Fixture;
+use dotkoke::*;
+
+#[test]
+fn install_records_backup_and_links() {
+    let fixture = Fixture::new();
+    let zshrc = fixture.write_dotfile(".zshrc", "new");
+    let vimrc = fixture.write_dotfile(".vimrc", "vim");
+    fixture.write_home(".zshrc", "old");
+
+    let executor = RecordingExecutor::new(fixture.config());
+    install(&executor).unwrap();
+
+    assert_eq!(
+        executor.into_operations(),
+        vec![
+            Operation::CreateSymlink {
+                from: vimrc,
+                to: fixture.home.join(".vimrc"),
+            },
+            Operation::RemoveFileFromHome {
+                path: fixture.home.join(".zshrc"),
+            },
+            Operation::CreateSymlink {
+                from: zshrc,
+                to: fixture.home.join(".zshrc"),
+            },
+        ]
+    );
+
+    // 記録のみで実際には操作しない．
+    assert_eq!(
+        fs::read_to_string(fixture.home.join(".zshrc")).unwrap(),
+        "old"
+    );
+    assert!(!fixture.home.join(".vimrc").exists());
+}
+
+#[test]
+fn add_records_copy() {
+    let fixture = Fixture::new();
+    let gitconfig = fixture.write_home(".gitconfig", "[user]\n");
+
+    let executor = RecordingExecutor::new(fixture.config());
+    add(&executor, &gitconfig, &AddOptions::default()).unwrap();
+
+    assert_eq!(
+        executor.into_operations(),
+        vec![Operation::Copy {
+            from: gitconfig,
+            to: fixture.dotfiles.join("home/.gitconfig"),
+        }]
+    );
+}
+
+#[test]
+fn remove_records_link_and_file_removal() {
+    let fixture = Fixture::new();
+    let zshrc = fixture.write_dotfile(".zshrc", "zsh");
+    std::os::unix::fs::symlink(&zshrc, fixture.home.join(".zshrc")).unwrap();
+
+    let executor = RecordingExecutor::new(fixture.config());
+    remove(&executor, &zshrc).unwrap();
+
+    assert_eq!(
+        executor.into_operations(),
+        vec![
+            Operation::RemoveSymlinkFromHome {
+                path: fixture.home.join(".zshrc"),
+            },
+            Operation::RemoveFileFromDotfilesHome { path: zshrc },
+        ]
+    );
+}
+
+#[test]
+fn plan_executor_captures_assumed_states() {
+    let fixture = Fixture::new();
+    let zshrc = fixture.write_dotfile(".zshrc", "zsh");
+
+    let executor = PlanExecutor::new(fixture.config());
+    install(&executor).unwrap();
+    let plan = executor.into_plan();
+
+    assert_eq!(plan.steps.len(), 1);
+    assert_eq!(
+        plan.steps[0].assumes,
+        vec![
+            PathState::capture(&zshrc),
+            PathState::capture(fixture.home.join(".zshrc")),
+        ]
+    );
+    plan.verify().unwrap();
+}
+
+#[test]
+fn install_skips_non_executable_scripts() {
+    let fixture = Fixture::new();
+    let scripts = fixture.dotfiles.join("scripts");
+    let executable = common::write_script(&scripts.join("once-executable.sh"), "true");
+    let plain = common::write(&scripts.join("once-plain.sh"), "#!/bin/sh\ntrue\n");
+
+    let executor = RecordingExecutor::new(fixture.config());
+    let report = install(&executor).unwrap();
+
+    assert_eq!(report.scripts, vec![executable.clone()]);
+    assert!(
+        report
+            .warnings
+            .contains(&format!("{} is not executable. skipped.", plain.display()))
+    );
+    assert_eq!(
+        executor.into_operations(),
+        vec![Operation::RunScript { path: executable }]
+    );
+}
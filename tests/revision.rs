@@ -0,0 +1,188 @@
+mod common;
+
+use std::{collections::BTreeSet, env, fs, path::PathBuf};
+
+use common::{Fixture, commit_all, git, write, write_script};
+
+/// `.zshrc`と`.vimrc`をコミットした後に`.vimrc`を削除してコミットしたレポジトリ．
+/// `(fixture, 最初のコミット, 次のコミット)`
+fn repo_with_history() -> (Fixture, String, String) {
+    let fixture = Fixture::new();
+    git(&fixture.dotfiles, ["init", "-q", "-b", "main"]);
+    fixture.write_dotfile(".zshrc", "zsh");
+    fixture.write_dotfile(".vimrc", "vim");
+    commit_all(&fixture.dotfiles, "first");
+    let first = git(&fixture.dotfiles, ["rev-parse", "HEAD"])
+        .trim()
+        .to_string();
+
+    fs::remove_file(fixture.dotfiles.join("home/.vimrc")).unwrap();
+    commit_all(&fixture.dotfiles, "second");
+    let second = git(&fixture.dotfiles, ["rev-parse", "HEAD"])
+        .trim()
+        .to_string();
+
+    (fixture, first, second)
+}
+
+fn install(fixture: &Fixture, args: &[&str]) {
+    let output = fixture.run(&[&["install"], args].concat(), &[]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn revisions(fixture: &Fixture) -> PathBuf {
+    fixture.root.join("cache/dotkoke/revisions")
+}
+
+#[test]
+fn dry_run_leaves_no_checkout() {
+    let (fixture, first, _) = repo_with_history();
+    let temporary_dirs = || {
+        fs::read_dir(env::temp_dir())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("dotkoke-"))
+            .map(|entry| entry.path())
+            .collect::<BTreeSet<_>>()
+    };
+    let before = temporary_dirs();
+
+    install(&fixture, &["--dry-run", "--rev", &first]);
+
+    assert!(!fixture.root.join("cache").exists());
+    assert!(temporary_dirs().is_subset(&before));
+    assert_eq!(
+        git(&fixture.dotfiles, ["worktree", "list"]).lines().count(),
+        1
+    );
+    assert!(fixture.home.join(".zshrc").symlink_metadata().is_err());
+}
+
+#[test]
+fn install_rev_links_into_checkout_and_prunes_unreferenced_ones() {
+    let (fixture, first, second) = repo_with_history();
+
+    install(&fixture, &["--rev", &first]);
+    let vimrc = fixture.home.join(".vimrc");
+    assert_eq!(
+        fs::read_link(&vimrc).unwrap(),
+        revisions(&fixture).join(&first).join("home/.vimrc")
+    );
+
+    install(&fixture, &["--rev", &second]);
+    assert_eq!(
+        fs::read_link(fixture.home.join(".zshrc")).unwrap(),
+        revisions(&fixture).join(&second).join("home/.zshrc")
+    );
+    // `.vimrc`のリンクが指しているうちは削除しない．
+    assert!(revisions(&fixture).join(&first).exists());
+    assert_eq!(fs::read_to_string(&vimrc).unwrap(), "vim");
+
+    fs::remove_file(&vimrc).unwrap();
+    install(&fixture, &["--rev", &second]);
+    assert!(!revisions(&fixture).join(&first).exists());
+    assert!(revisions(&fixture).join(&second).exists());
+}
+
+#[test]
+fn plan_out_keeps_installed_revision() {
+    let (fixture, first, second) = repo_with_history();
+    install(&fixture, &["--rev", &first]);
+
+    let plan = fixture.root.join("plan.json");
+    install(
+        &fixture,
+        &["--rev", &second, "--plan-out", plan.to_str().unwrap()],
+    );
+
+    assert!(revisions(&fixture).join(&first).exists());
+    assert_eq!(
+        fs::read_to_string(fixture.home.join(".zshrc")).unwrap(),
+        "zsh"
+    );
+
+    // 適用後は計画のrevisionを指し，使われなくなったものを削除する．
+    let output = fixture.run(&["apply", plan.to_str().unwrap()], &[]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        fs::read_link(fixture.home.join(".zshrc")).unwrap(),
+        revisions(&fixture).join(&second).join("home/.zshrc")
+    );
+}
+
+#[test]
+fn failed_pre_hook_keeps_installed_revision() {
+    let (fixture, first, second) = repo_with_history();
+    install(&fixture, &["--rev", &first]);
+    write_script(&fixture.dotfiles.join("hooks/pre-install"), "exit 1");
+
+    let output = fixture.run(&["install", "--rev", &second], &[]);
+
+    assert!(!output.status.success());
+    assert!(revisions(&fixture).join(&first).exists());
+    assert_eq!(
+        fs::read_link(fixture.home.join(".zshrc")).unwrap(),
+        revisions(&fixture).join(&first).join("home/.zshrc")
+    );
+    assert_eq!(
+        fs::read_to_string(fixture.home.join(".zshrc")).unwrap(),
+        "zsh"
+    );
+}
+
+#[test]
+fn install_rev_removes_legacy_worktrees() {
+    let (fixture, first, second) = repo_with_history();
+    let legacy = fixture.root.join("cache/dotkoke/worktrees").join(&first);
+    git(
+        &fixture.dotfiles,
+        [
+            "worktree",
+            "add",
+            "--detach",
+            "--quiet",
+            legacy.to_str().unwrap(),
+            &first,
+        ],
+    );
+
+    install(&fixture, &["--rev", &second]);
+
+    assert!(!legacy.exists());
+    assert_eq!(
+        git(&fixture.dotfiles, ["worktree", "list"]).lines().count(),
+        1
+    );
+}
+
+#[test]
+fn install_rev_links_link_sources_from_checkout() {
+    let fixture =
+        Fixture::with_config("[[link]]\nsrc = \"shared/gitconfig\"\ndst = \".gitconfig\"\n");
+    git(&fixture.dotfiles, ["init", "-q", "-b", "main"]);
+    fixture.write_dotfile(".zshrc", "zsh");
+    let gitconfig = write(&fixture.dotfiles.join("shared/gitconfig"), "old");
+    commit_all(&fixture.dotfiles, "first");
+    let first = git(&fixture.dotfiles, ["rev-parse", "HEAD"])
+        .trim()
+        .to_string();
+    write(&gitconfig, "new");
+    commit_all(&fixture.dotfiles, "second");
+
+    install(&fixture, &["--rev", &first]);
+
+    let link = fixture.home.join(".gitconfig");
+    assert_eq!(
+        fs::read_link(&link).unwrap(),
+        revisions(&fixture).join(&first).join("shared/gitconfig")
+    );
+    assert_eq!(fs::read_to_string(&link).unwrap(), "old");
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::{Fixture, write_script};
+
+/// 呼び出されるたびに`count`に1行追記し，入力をそのまま出力する復号コマンド．
+struct StubDecrypt {
+    fixture: Fixture,
+    stub: PathBuf,
+}
+
+impl StubDecrypt {
+    fn new() -> Self {
+        let fixture = Fixture::with_config("[secrets]\ndecrypt_command = '\"$STUB_DECRYPT\"'\n");
+        let stub = write_script(
+            &fixture.root.join("decrypt.sh"),
+            &format!("echo x >> {:?}\ncat", fixture.root.join("count")),
+        );
+        fixture.write_dotfile(".netrc.enc", "machine example.com");
+        StubDecrypt { fixture, stub }
+    }
+
+    fn install(&self, args: &[&str]) -> String {
+        let output = self.fixture.run(
+            &[&["install"], args].concat(),
+            &[("STUB_DECRYPT", self.stub.as_path())],
+        );
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    fn count(&self) -> usize {
+        fs::read_to_string(self.fixture.root.join("count"))
+            .map(|count| count.lines().count())
+            .unwrap_or(0)
+    }
+
+    fn netrc(&self) -> PathBuf {
+        self.fixture.home.join(".netrc")
+    }
+}
+
+#[test]
+fn dry_run_and_plan_do_not_decrypt() {
+    let stub = StubDecrypt::new();
+    let plan = stub.fixture.root.join("plan.json");
+
+    assert!(stub.install(&["--dry-run"]).contains("decrypted:"));
+    stub.install(&["--plan-out", plan.to_str().unwrap()]);
+
+    assert_eq!(stub.count(), 0);
+    assert!(!stub.netrc().exists());
+}
+
+#[test]
+fn install_decrypts_once_and_skips_up_to_date() {
+    let stub = StubDecrypt::new();
+
+    assert!(stub.install(&[]).contains("decrypted:"));
+    assert_eq!(stub.count(), 1);
+    assert_eq!(
+        fs::read_to_string(stub.netrc()).unwrap(),
+        "machine example.com"
+    );
+
+    // 記録したハッシュと比較するので，復号コマンドは実行しない．
+    assert!(!stub.install(&["--dry-run"]).contains("decrypted:"));
+    assert!(!stub.install(&[]).contains("decrypted:"));
+    assert_eq!(stub.count(), 1);
+}
+
+#[test]
+fn install_decrypts_again_when_source_or_installed_file_changes() {
+    let stub = StubDecrypt::new();
+    stub.install(&[]);
+
+    stub.fixture
+        .write_dotfile(".netrc.enc", "machine example.org");
+    assert!(stub.install(&["--dry-run"]).contains("decrypted:"));
+    assert_eq!(stub.count(), 1);
+    stub.install(&[]);
+    assert_eq!(stub.count(), 2);
+    assert_eq!(
+        fs::read_to_string(stub.netrc()).unwrap(),
+        "machine example.org"
+    );
+
+    fs::write(stub.netrc(), "edited").unwrap();
+    let output = stub.install(&[]);
+    assert!(output.contains("backed up:"));
+    assert!(output.contains("decrypted:"));
+    assert_eq!(stub.count(), 3);
+}
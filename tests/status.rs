@@ -0,0 +1,41 @@
+mod common;
+
+use common::{Fixture, commit_all, git, write};
+use dotkoke::*;
+
+#[test]
+fn status_without_git_has_no_repo() {
+    let fixture = Fixture::new();
+    fixture.write_dotfile(".zshrc", "zsh");
+
+    let report = status(fixture.config()).unwrap();
+    assert_eq!(report.repo, None);
+}
+
+#[test]
+fn status_in_repo_without_commits() {
+    let fixture = Fixture::new();
+    let zshrc = fixture.write_dotfile(".zshrc", "zsh");
+    fixture.write_dotfile(".vimrc", "vim");
+    git(&fixture.dotfiles, ["init", "--quiet"]);
+    git(&fixture.dotfiles, ["add", "home/.zshrc"]);
+
+    let report = status(fixture.config()).unwrap();
+    let repo = report.repo.unwrap();
+    assert_eq!(repo.uncommitted_changes, 2);
+    assert_eq!(repo.unpushed_commits, None);
+    assert_eq!(repo.modified, vec![zshrc]);
+}
+
+#[test]
+fn modified_link_source_is_reported() {
+    let fixture =
+        Fixture::with_config("[[link]]\nsrc = \"shared/gitconfig\"\ndst = \".gitconfig\"\n");
+    let gitconfig = write(&fixture.dotfiles.join("shared/gitconfig"), "old");
+    git(&fixture.dotfiles, ["init", "--quiet"]);
+    commit_all(&fixture.dotfiles, "first");
+    write(&gitconfig, "new");
+
+    let report = status(fixture.config()).unwrap();
+    assert_eq!(report.repo.unwrap().modified, vec![gitconfig]);
+}
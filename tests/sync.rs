@@ -0,0 +1,175 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::{Fixture, commit_all, git, write, write_script};
+use dotkoke::*;
+
+/// dotfilesレポジトリと，そのupstreamのbareレポジトリと，upstreamに別の変更を加えるためのclone．
+struct Remote {
+    fixture: Fixture,
+    other: PathBuf,
+}
+
+impl Remote {
+    /// `.vimrc`をコミットしてupstreamへpushし，installしておく．
+    fn new() -> Self {
+        Self::with_config("")
+    }
+
+    /// `shared/gitconfig`もコミットしておき，`extra`を加えた設定ファイルでinstallしておく．
+    fn with_config(extra: &str) -> Self {
+        let fixture = Fixture::with_config(extra);
+        write(&fixture.dotfiles.join("shared/gitconfig"), "[user]\n");
+        let bare = fixture.root.join("remote.git");
+        let other = fixture.root.join("other");
+
+        git(
+            &fixture.root,
+            ["init", "-q", "--bare", "-b", "main", "remote.git"],
+        );
+        git(&fixture.dotfiles, ["init", "-q", "-b", "main"]);
+        fixture.write_dotfile(".vimrc", "vim");
+        commit_all(&fixture.dotfiles, "init");
+        git(
+            &fixture.dotfiles,
+            ["remote", "add", "origin", bare.to_str().unwrap()],
+        );
+        git(&fixture.dotfiles, ["push", "-q", "-u", "origin", "main"]);
+        git(
+            &fixture.root,
+            ["clone", "-q", bare.to_str().unwrap(), "other"],
+        );
+
+        install(&RealExecutor::new(fixture.config())).unwrap();
+
+        Remote { fixture, other }
+    }
+
+    fn push_from_other(&self) {
+        commit_all(&self.other, "other");
+        git(&self.other, ["push", "-q"]);
+    }
+
+    fn commit_local(&self) {
+        commit_all(&self.fixture.dotfiles, "local");
+    }
+}
+
+#[test]
+fn sync_behind_fast_forwards_and_removes_deleted_links() {
+    let remote = Remote::new();
+    let fixture = &remote.fixture;
+    std::fs::remove_file(remote.other.join("home/.vimrc")).unwrap();
+    write(&remote.other.join("home/.zshrc"), "zsh");
+    remote.push_from_other();
+
+    let report = sync(&RealExecutor::new(fixture.config()), false).unwrap();
+
+    assert!(!report.up_to_date);
+    assert_eq!(
+        report.changes,
+        vec![
+            Change {
+                kind: ChangeKind::Deleted,
+                path: fixture.dotfiles.join("home/.vimrc"),
+            },
+            Change {
+                kind: ChangeKind::Added,
+                path: fixture.dotfiles.join("home/.zshrc"),
+            },
+        ]
+    );
+    assert_eq!(report.removed_links, vec![fixture.home.join(".vimrc")]);
+    assert!(fixture.home.join(".vimrc").symlink_metadata().is_err());
+    assert_eq!(
+        std::fs::read_link(fixture.home.join(".zshrc")).unwrap(),
+        fixture.dotfiles.join("home/.zshrc")
+    );
+}
+
+#[test]
+fn sync_ahead_keeps_unpushed_files() {
+    let remote = Remote::new();
+    let fixture = &remote.fixture;
+    fixture.write_dotfile(".gitconfig", "[user]\n");
+    remote.commit_local();
+    install(&RealExecutor::new(fixture.config())).unwrap();
+
+    let report = sync(&RealExecutor::new(fixture.config()), false).unwrap();
+
+    assert!(report.up_to_date);
+    assert_eq!(report.changes, vec![]);
+    assert_eq!(report.removed_links, Vec::<PathBuf>::new());
+    assert_eq!(report.clean.removed_links, Vec::<PathBuf>::new());
+    assert_eq!(
+        std::fs::read_link(fixture.home.join(".gitconfig")).unwrap(),
+        fixture.dotfiles.join("home/.gitconfig")
+    );
+}
+
+#[test]
+fn sync_diverged_is_error() {
+    let remote = Remote::new();
+    let fixture = &remote.fixture;
+    write(&remote.other.join("home/.zshrc"), "zsh");
+    remote.push_from_other();
+    fixture.write_dotfile(".gitconfig", "[user]\n");
+    remote.commit_local();
+
+    assert!(matches!(
+        sync(&RealExecutor::new(fixture.config()), false),
+        Err(Error::DivergedRepo { .. })
+    ));
+    assert!(!fixture.dotfiles.join("home/.zshrc").exists());
+}
+
+#[test]
+fn sync_removes_links_to_deleted_link_src() {
+    let remote =
+        Remote::with_config("[[link]]\nsrc = \"shared/gitconfig\"\ndst = \".gitconfig\"\n");
+    let fixture = &remote.fixture;
+    let gitconfig = fixture.home.join(".gitconfig");
+    assert_eq!(
+        std::fs::read_link(&gitconfig).unwrap(),
+        fixture.dotfiles.join("shared/gitconfig")
+    );
+
+    std::fs::remove_file(remote.other.join("shared/gitconfig")).unwrap();
+    remote.push_from_other();
+
+    let report = sync(&RealExecutor::new(fixture.config()), false).unwrap();
+
+    assert_eq!(
+        report.changes,
+        vec![Change {
+            kind: ChangeKind::Deleted,
+            path: fixture.dotfiles.join("shared/gitconfig"),
+        }]
+    );
+    assert_eq!(report.removed_links, vec![gitconfig.clone()]);
+    assert!(gitconfig.symlink_metadata().is_err());
+}
+
+#[test]
+fn failing_pre_install_hook_aborts_sync_install() {
+    let remote = Remote::new();
+    let fixture = &remote.fixture;
+    write(&remote.other.join("home/.zshrc"), "zsh");
+    remote.push_from_other();
+    let log = fixture.root.join("hook.log");
+    write_script(
+        &fixture.dotfiles.join("hooks/pre-install"),
+        &format!("echo \"$DOTKOKE_CHANGED_PATHS\" > {:?}\nexit 1", log),
+    );
+
+    assert!(matches!(
+        sync(&RealExecutor::new(fixture.config()), false),
+        Err(Error::Hook { .. })
+    ));
+    assert_eq!(
+        std::fs::read_to_string(&log).unwrap(),
+        format!("{}\n", fixture.home.join(".zshrc").display())
+    );
+    assert!(fixture.home.join(".zshrc").symlink_metadata().is_err());
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use std::fs;
+
+use common::Fixture;
+use dotkoke::*;
+
+#[test]
+fn triggers_run_only_for_changed_paths() {
+    let fixture = Fixture::with_config(
+        "[secrets]\ndecrypt_command = \"cat\"\n\n[triggers]\n\".*\" = \"true\"\n",
+    );
+    fixture.write_dotfile(".zshrc", "zsh");
+    install(&RealExecutor::new(fixture.config())).unwrap();
+
+    // .zshrcはリンク済み．.vimrcはリンクを作成し，.bashrcは退避してリンクし，.netrcは復号する．
+    fixture.write_dotfile(".vimrc", "vim");
+    fixture.write_dotfile(".bashrc", "new");
+    fixture.write_home(".bashrc", "old");
+    fixture.write_dotfile(".netrc.enc", "machine example.com");
+
+    let report = install(&RealExecutor::new(fixture.config())).unwrap();
+
+    assert_eq!(
+        report.triggers,
+        vec![TriggerRun {
+            command: "true".to_string(),
+            paths: [".bashrc", ".netrc", ".vimrc"]
+                .map(|rel| fixture.home.join(rel))
+                .to_vec(),
+        }]
+    );
+
+    // すべてリンク済み・復号済みなら何も実行しない．
+    let report = install(&RealExecutor::new(fixture.config())).unwrap();
+    assert_eq!(report.triggers, vec![]);
+    assert_eq!(
+        fs::read_to_string(fixture.home.join(".netrc")).unwrap(),
+        "machine example.com"
+    );
+}